@@ -10,3 +10,143 @@ check-device = Make sure {$deviceName} is turned on, in range, and is ready to p
 try-again = Try Again
 discoverable = Discoverable
 pairable = Pairable
+set-primary = Set as primary device
+unset-primary = Unset as primary device
+auto-connect = Trusted, connects automatically
+reconnecting = Reconnecting to the Bluetooth service...
+reconnect = Reconnect
+adapter-info = This computer
+pairing-request = Bluetooth pairing request
+open-applet = Open the Bluetooth applet to respond.
+signal-history = Signal strength
+wake-allowed = Wake this computer from sleep
+no-devices-nearby = { $category ->
+    [audio] No audio devices nearby. Put your headphones or speaker in pairing mode.
+    [input] No keyboards or mice nearby. Put your device in pairing mode.
+   *[other] No devices nearby. Make sure your device is in pairing mode.
+}
+connect-timeout-remaining = {$seconds}s
+connect-error = { $kind ->
+    [authentication] Pairing was rejected. Try removing the device and pairing it again.
+    [timeout] The device took too long to respond.
+    [profile] The device doesn't offer a service this computer can use.
+    [range] The device is turned off or out of range.
+    [busy] The device is busy with another connection.
+   *[other] Couldn't connect to the device.
+}
+on-adapter = Paired with {$adapter}
+snooze-notifications = Snooze notifications
+snooze-hours = {$hours}h
+notifications-snoozed = Notifications snoozed for {$minutes} min
+resume = Resume
+bluetooth-on = Bluetooth on
+bluetooth-off = Bluetooth off
+no-devices-connected = No devices connected
+forget-device = Forget
+forget-device-confirm = Forget {$deviceName}? It will need to be paired again to connect.
+headset-profile = Use microphone (lowers audio quality)
+rename = Rename
+rename-placeholder = Leave empty to use the device's own name
+battery-unsupported = Battery reporting not supported
+battery-not-reported = Battery level not reported yet
+trusted = Trusted
+pair = Pair
+filter-devices = Search devices
+enter-pin = Enter the PIN for {$deviceName}. It may be printed on the device or in its manual, or try 0000.
+type-passkey = Type this code on {$deviceName}, then press Enter on it if needed
+passkey-entered = {$entered} of {$total} digits entered
+authorize-service = {$deviceName} wants to use {$service}
+authorize-pairing = {$deviceName} wants to pair with this computer
+allow = Allow
+always-allow = Always allow
+deny = Deny
+bluetooth-unavailable = Bluetooth unavailable
+no-adapter = No Bluetooth adapter found
+device-connected = Device connected
+device-disconnected = Device disconnected
+connect-failed = Couldn't connect to {$deviceName}
+unpair-and-retry = Unpair and try again
+battery-number = Battery {$number}
+disconnect-all = Disconnect all devices
+make-visible = Make visible
+visible-remaining = Visible to nearby devices for {$time}
+accept-pairing = Accept new pairings
+hard-blocked = Blocked by a hardware switch or airplane mode
+scan-stopped = Stopped looking for devices
+scan-again = Scan again
+confirm-remaining = {$seconds}s left to confirm
+copy-address = Copy address
+group-by-type = Group by type
+device-category = { $category ->
+    [audio] Audio
+    [input] Input devices
+    [phone] Phones
+    [computer] Computers
+   *[other] Other
+}
+tooltip-status = Status: { $status ->
+    [connected] Connected
+    [connecting] Connecting
+    [disconnecting] Disconnecting
+   *[disconnected] Not connected
+}
+tooltip-battery = Battery: {$percent}%
+tooltip-address = Address: {$address}
+tooltip-type = Type: {$type}
+paired-devices = My devices
+sort-by-recent = Most recently connected first
+last-connected-now = Last connected just now
+last-connected-minutes = Last connected { $count ->
+    [one] a minute
+   *[other] {$count} minutes
+} ago
+last-connected-hours = Last connected { $count ->
+    [one] an hour
+   *[other] {$count} hours
+} ago
+last-connected-days = Last connected { $count ->
+    [one] yesterday
+   *[other] {$count} days ago
+}
+warning-title = Something went wrong
+dismiss = Dismiss
+refresh-devices = Refresh device list
+battery-low = {$deviceName} battery low
+battery-remaining = {$percent}% remaining
+connected-for = Connected for { $hours ->
+    [0] {$minutes}m
+   *[other] {$hours}h {$minutes}m
+}
+connect = Connect
+disconnect = Disconnect
+trust = Trust
+untrust = Don't trust
+connecting = Connecting…
+disconnecting = Disconnecting…
+adapter-alias = Name shown to other devices
+adapter-alias-placeholder = Leave empty to use the computer's name
+turning-on = Turning on…
+turning-off = Turning off…
+connect-profile = Connect only
+pair-only = Pair without connecting
+enter-airplane-mode = Enter airplane mode
+leave-airplane-mode = Leave airplane mode
+set-nickname = Set nickname
+change-nickname = Change nickname
+clear-nickname = Clear nickname
+save = Save
+compact-list = Compact list
+block = Block
+unblock = Unblock
+blocked = Blocked
+device-info = Device information
+loading = Loading…
+address-type = Address type: { $type }
+device-class = Device class: { $class }
+device-appearance = Appearance: { $appearance }
+vendor-product = Vendor ID { $vendor }, product ID { $product }
+services = Services
+filter-paired = Paired
+filter-connected = Connected
+filter-audio = Audio
+searching = Searching
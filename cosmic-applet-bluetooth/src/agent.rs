@@ -1,10 +1,19 @@
-use bluer::agent::{Agent, ReqError, RequestConfirmation};
+use bluer::agent::{
+    Agent, AuthorizeService, DisplayPasskey, DisplayPinCode, ReqError, RequestAuthorization,
+    RequestConfirmation, RequestPasskey, RequestPinCode,
+};
 use futures::FutureExt;
 use tokio::sync::{mpsc, oneshot};
 
 #[derive(Debug)]
 pub enum AgentEvent {
     RequestConfirmation(u32, bluer::Address, oneshot::Sender<bool>),
+    RequestPinCode(bluer::Address, oneshot::Sender<String>),
+    RequestPasskey(bluer::Address, oneshot::Sender<u32>),
+    /// passkey to type on the device, and how many digits have been typed if the device reports it
+    DisplayPasskey(bluer::Address, String, Option<u16>),
+    /// the device wants to use a service, or to pair without a code when the service is `None`
+    AuthorizeService(bluer::Address, Option<bluer::Uuid>, oneshot::Sender<bool>),
 }
 
 /// Bluetooth authorization agent (handles generating/displaying pin codes and passkeys)
@@ -18,6 +27,53 @@ pub fn create_agent(output: mpsc::UnboundedSender<AgentEvent>) -> Agent {
                 request_confirmation(req, output).boxed()
             })
         }),
+        request_pin_code: Some({
+            let output = output.clone();
+            Box::new(move |req| {
+                let output = output.clone();
+                request_pin_code(req, output).boxed()
+            })
+        }),
+        request_passkey: Some({
+            let output = output.clone();
+            Box::new(move |req| {
+                let output = output.clone();
+                request_passkey(req, output).boxed()
+            })
+        }),
+        display_passkey: Some({
+            let output = output.clone();
+            Box::new(move |req: DisplayPasskey| {
+                let code = format!("{:06}", req.passkey);
+                _ = output.send(AgentEvent::DisplayPasskey(
+                    req.device,
+                    code,
+                    Some(req.entered),
+                ));
+                async { Ok(()) }.boxed()
+            })
+        }),
+        request_authorization: Some({
+            let output = output.clone();
+            Box::new(move |req: RequestAuthorization| {
+                let output = output.clone();
+                authorize(req.device, None, output).boxed()
+            })
+        }),
+        authorize_service: Some({
+            let output = output.clone();
+            Box::new(move |req: AuthorizeService| {
+                let output = output.clone();
+                authorize(req.device, Some(req.service), output).boxed()
+            })
+        }),
+        display_pin_code: Some({
+            let output = output.clone();
+            Box::new(move |req: DisplayPinCode| {
+                _ = output.send(AgentEvent::DisplayPasskey(req.device, req.pincode, None));
+                async { Ok(()) }.boxed()
+            })
+        }),
         ..Default::default()
     }
 }
@@ -34,4 +90,44 @@ async fn request_confirmation(req: RequestConfirmation, output: mpsc::UnboundedS
         Ok(true) => Ok(()),
         _ => Err(ReqError::Rejected)
     }
-}
\ No newline at end of file
+}
+
+/// The device has no display, user types the PIN it expects (often printed on it, or 0000)
+async fn request_pin_code(req: RequestPinCode, output: mpsc::UnboundedSender<AgentEvent>) -> Result<String, ReqError> {
+    tracing::info!("agent received pin code request...");
+
+    let (tx, rx) = oneshot::channel();
+
+    _ = output.send(AgentEvent::RequestPinCode(req.device, tx));
+
+    rx.await.map_err(|_| ReqError::Rejected)
+}
+
+/// Like a PIN code, but numeric
+async fn request_passkey(req: RequestPasskey, output: mpsc::UnboundedSender<AgentEvent>) -> Result<u32, ReqError> {
+    tracing::info!("agent received passkey request...");
+
+    let (tx, rx) = oneshot::channel();
+
+    _ = output.send(AgentEvent::RequestPasskey(req.device, tx));
+
+    rx.await.map_err(|_| ReqError::Rejected)
+}
+
+/// User allows or denies a device using a service, or pairing without a code
+async fn authorize(
+    device: bluer::Address,
+    service: Option<bluer::Uuid>,
+    output: mpsc::UnboundedSender<AgentEvent>,
+) -> Result<(), ReqError> {
+    tracing::info!("agent received authorization request...");
+
+    let (tx, rx) = oneshot::channel();
+
+    _ = output.send(AgentEvent::AuthorizeService(device, service, tx));
+
+    match rx.await {
+        Ok(true) => Ok(()),
+        _ => Err(ReqError::Rejected),
+    }
+}
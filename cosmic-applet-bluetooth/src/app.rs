@@ -1,10 +1,24 @@
-use std::{collections::HashMap, sync::LazyLock};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::LazyLock,
+    time::Duration,
+};
 
 use crate::{
-    config,
-    device::{BluetoothDevice, ConnectionStatus},
+    config::{
+        self, BatteryDisplay, BatteryLevel, BluetoothAppletConfig, DeviceSort, PairedSort,
+        TooltipVerbosity,
+    },
+    dbus,
+    device::{
+        BluetoothDevice, ConnectionStatus, DEFAULT_DEVICE_ICON, DeviceCategory, DeviceUpdate,
+        SIGNAL_HISTORY_LEN, service_name,
+    },
     fl,
-    worker::{self, WorkerEvent, WorkerRequest},
+    notification::{self, Notification},
+    worker::{
+        self, AdapterInfo, ConnectError, ConnectErrorKind, DeviceInfo, WorkerEvent, WorkerRequest,
+    },
 };
 use cosmic::{
     Element,
@@ -14,15 +28,83 @@ use cosmic::{
         token::subscription::{self, TokenRequest, TokenUpdate},
     },
     cctk::sctk::reexports::calloop,
-    iced::{Subscription, platform_specific::shell::wayland::commands::popup},
+    cosmic_config::{self, CosmicConfigEntry},
+    cosmic_theme::palette::Srgba,
+    iced::{
+        Limits, Subscription,
+        event::{self, listen_with},
+        keyboard,
+        platform_specific::shell::wayland::commands::popup,
+    },
     iced_core::{Alignment, Length, window},
-    iced_widget::{Column, column, row, scrollable},
-    widget::{button, container, divider, icon, text},
+    iced_widget::{Column, Row, column, row, scrollable},
+    surface,
+    widget::{
+        Id, button, container, divider, icon, mouse_area, svg, text, text_input, toggler, tooltip,
+    },
 };
 use cosmic_time::{Instant, Timeline, anim, id};
 use tokio::sync::mpsc;
 
 static BLUETOOTH_ENABLED: LazyLock<id::Toggler> = LazyLock::new(id::Toggler::unique);
+// only one device's details are shown at a time, so the detail togglers can share ids
+static WAKE_ALLOWED: LazyLock<id::Toggler> = LazyLock::new(id::Toggler::unique);
+static HEADSET_PROFILE: LazyLock<id::Toggler> = LazyLock::new(id::Toggler::unique);
+static TRUSTED: LazyLock<id::Toggler> = LazyLock::new(id::Toggler::unique);
+static DISCOVERABLE: LazyLock<id::Toggler> = LazyLock::new(id::Toggler::unique);
+static PAIRABLE: LazyLock<id::Toggler> = LazyLock::new(id::Toggler::unique);
+static PAIRED_LIST: LazyLock<Id> = LazyLock::new(|| Id::new("paired-list"));
+static NEARBY_LIST: LazyLock<Id> = LazyLock::new(|| Id::new("nearby-list"));
+
+/// Minimum time between automatic refreshes when the worker and app disagree about the device list
+const RESYNC_DEBOUNCE: Duration = Duration::from_secs(5);
+
+/// Lengths offered for snoozing notifications, in hours
+const SNOOZE_HOURS: [u64; 3] = [1, 4, 8];
+
+/// Failed connects in a row after which re-pairing the device is suggested
+const FAILURES_BEFORE_UNPAIR: u32 = 3;
+
+/// Worker warnings shown at once, older ones make way for new ones
+const MAX_WARNINGS: usize = 3;
+
+/// How long a worker warning stays up unless it's dismissed
+const WARNING_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long turning bluetooth on or off may take before the toggle gives up waiting and shows the actual state
+const POWER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Device lists longer than this scroll instead of growing the popup
+const SCROLL_AFTER_DEVICES: usize = 10;
+
+/// Quick filters over the device lists, a device has to match every one that's set
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct FilterFlags(u8);
+
+impl FilterFlags {
+    const PAIRED: Self = Self(1);
+    const CONNECTED: Self = Self(1 << 1);
+    const AUDIO: Self = Self(1 << 2);
+
+    fn contains(self, flag: Self) -> bool {
+        self.0 & flag.0 == flag.0
+    }
+
+    fn toggle(&mut self, flag: Self) {
+        self.0 ^= flag.0;
+    }
+
+    fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    fn matches(self, dev: &BluetoothDevice) -> bool {
+        (!self.contains(Self::PAIRED) || dev.is_paired)
+            && (!self.contains(Self::CONNECTED)
+                || matches!(dev.status, ConnectionStatus::Connected))
+            && (!self.contains(Self::AUDIO) || dev.category == DeviceCategory::Audio)
+    }
+}
 
 pub fn run() -> cosmic::iced::Result {
     cosmic::applet::run::<CosmicBluetoothApplet>(())
@@ -31,14 +113,95 @@ pub fn run() -> cosmic::iced::Result {
 #[derive(Default)]
 struct CosmicBluetoothApplet {
     core: cosmic::app::Core,
+    config: BluetoothAppletConfig,
+    config_helper: Option<cosmic_config::Config>,
     device_map: Option<HashMap<bluer::Address, BluetoothDevice>>,
     enabled: bool,
+    /// the state the bluetooth toggler is showing or animating towards
+    toggle_target: bool,
+    /// when bluetooth was asked to turn on or off, until the adapter confirms it. The toggle ignores clicks meanwhile
+    power_pending_since: Option<std::time::Instant>,
+    adapter_info: Option<AdapterInfo>,
+    adapters: Vec<String>,
+    reconnecting: bool,
+    /// the user asked for the device list to be rebuilt and it hasn't arrived yet
+    refreshing: bool,
+    /// the last worker failure, set until a new worker is ready
+    worker_error: Option<String>,
+    /// recent non-fatal worker failures, oldest first, with when they arrived
+    warnings: VecDeque<(String, std::time::Instant)>,
+    /// no adapter was found, the worker starts once one is plugged in
+    no_adapter: bool,
+    /// other devices can find this computer
+    discoverable: bool,
+    /// when bluez turns discoverability off again, `None` if it stays on
+    discoverable_until: Option<std::time::Instant>,
+    /// incoming pairing requests are accepted
+    pairable: bool,
+    /// the radio is blocked by a hardware switch or airplane mode
+    hard_blocked: bool,
+    /// the radio is soft-blocked through rfkill, by airplane mode or another tool
+    soft_blocked: bool,
+    /// the popup's scan stopped by itself, until the user scans again
+    scan_timed_out: bool,
+    /// since when the adapter has been scanning, by its own account
+    discovering_since: Option<std::time::Instant>,
     worker_tx: Option<mpsc::UnboundedSender<WorkerRequest>>,
+    /// notification ids of pairing requests shown while the popup was closed
+    pairing_notifications: HashMap<bluer::Address, u32>,
+    /// how often the worker and app model disagreed, for diagnostics
+    desync_count: u32,
+    /// devices the app asked the worker for after hearing of them, and when
+    fetched_devices: HashMap<bluer::Address, std::time::Instant>,
+    last_resync: Option<std::time::Instant>,
+    /// informational notifications are held back until then, pairing requests still come through
+    snoozed_until: Option<std::time::Instant>,
     token_tx: Option<calloop::channel::Sender<TokenRequest>>,
 
     // UI state
     popup: Option<window::Id>,
-    show_visible_devices: bool,
+    expanded_device: Option<bluer::Address>,
+    /// how far each device list is scrolled, restored whenever the list is rebuilt
+    scroll_offsets: HashMap<Id, scrollable::AbsoluteOffset>,
+    /// device whose technical details are shown, they're fetched each time they're opened
+    device_info_shown: Option<bluer::Address>,
+    device_info: HashMap<bluer::Address, DeviceInfo>,
+    /// device whose raw connect error is shown
+    expanded_error: Option<bluer::Address>,
+    /// device whose action menu is open
+    device_menu: Option<bluer::Address>,
+    /// device row the arrow keys moved to, Enter does what clicking it would
+    focused_device: Option<bluer::Address>,
+    /// device the user asked to forget, waiting for them to confirm
+    confirm_forget: Option<bluer::Address>,
+    /// device being renamed, and the name typed so far
+    renaming: Option<(bluer::Address, String)>,
+    /// device getting a nickname, and the nickname typed so far
+    nicknaming: Option<(bluer::Address, String)>,
+    /// the adapter name typed so far, until it's submitted
+    adapter_alias: Option<String>,
+    /// nearby device selected for pairing, when pairing needs confirming
+    pair_candidate: Option<bluer::Address>,
+    /// devices waiting for a PIN to be typed in, and the PIN typed so far
+    pin_entry: HashMap<bluer::Address, String>,
+    /// codes to type on devices being paired, with the number of digits typed so far when known
+    displayed_passkeys: HashMap<bluer::Address, (String, Option<u16>)>,
+    /// devices waiting to be allowed to use a service, or to pair when the service is `None`, oldest first
+    authorizations: Vec<(bluer::Address, Option<bluer::Uuid>)>,
+    /// devices waiting for the user to compare a pairing code, oldest first, with the code and when the pairing
+    /// is given up
+    pending_confirmations: Vec<(bluer::Address, String, std::time::Instant)>,
+    /// devices the user was told have a low battery, until it's charged past the caution threshold
+    low_battery_notified: HashSet<bluer::Address>,
+    /// failed connects in a row for each device
+    connect_failures: HashMap<bluer::Address, u32>,
+    /// search text for the device lists
+    filter: String,
+    /// quick filters, cleared along with the search text
+    filter_flags: FilterFlags,
+    show_adapter_info: bool,
+    /// logical height of the output the panel is on, once the compositor has told us
+    output_height: Option<f32>,
     timeline: Timeline,
 }
 
@@ -47,225 +210,2330 @@ pub enum Message {
     TogglePopup,
     OpenSettings,
     ToggleBluetooth(cosmic_time::chain::Toggler, bool),
+    /// middle click on the panel icon, toggles bluetooth without opening the popup
+    ToggleBluetoothQuick,
+    /// soft-block bluetooth until airplane mode is left again
+    SetAirplane(bool),
     ToggleVisibleDevices(bool),
+    /// rebuild the device list from scratch
+    RefreshDevices,
     Frame(Instant),
     BluetoothEvent(WorkerEvent),
     Token(TokenUpdate),
     Request(WorkerRequest),
     CloseRequested(window::Id),
     ConfirmCode(bluer::Address, bool),
+    ConfigChanged(BluetoothAppletConfig),
+    ToggleDeviceDetails(bluer::Address),
+    ToggleDeviceInfo(bluer::Address),
+    Scrolled(Id, scrollable::AbsoluteOffset),
+    SetPrimaryDevice(Option<bluer::Address>),
+    ToggleAdapterInfo,
+    CopyAddress(bluer::Address),
+    PairingNotified(bluer::Address, Option<u32>),
+    NotificationAction(u32, String),
+    SetWakeAllowed(cosmic_time::chain::Toggler, bluer::Address, bool),
+    SetHeadsetProfile(cosmic_time::chain::Toggler, bluer::Address, bool),
+    SetTrusted(cosmic_time::chain::Toggler, bluer::Address, bool),
+    ToggleErrorDetails(bluer::Address),
+    /// silence notifications for a while, `None` resumes them
+    SnoozeNotifications(Option<Duration>),
+    Surface(surface::Action),
+    /// ask to confirm forgetting a device, `None` cancels
+    ConfirmForget(Option<bluer::Address>),
+    /// open a device's action menu, or close it
+    DeviceMenu(Option<bluer::Address>),
+    /// arrow keys, moves to the next device row when `true`, the previous one otherwise
+    MoveFocus(bool),
+    /// Enter on a focused device row
+    ActivateFocused,
+    ForgetDevice(bluer::Address),
+    WindowFocused(window::Id),
+    /// an output's name and logical height
+    OutputHeight(Option<String>, f32),
+    StartRename(bluer::Address),
+    RenameInput(String),
+    SubmitRename(bluer::Address, String),
+    CancelRename,
+    StartNickname(bluer::Address),
+    NicknameInput(String),
+    /// an empty nickname removes it
+    SubmitNickname(bluer::Address, String),
+    CancelNickname,
+    AdapterAliasInput(String),
+    SubmitAdapterAlias(String),
+    /// select a nearby device to pair, `None` deselects
+    SelectPairCandidate(Option<bluer::Address>),
+    SetNearbySort(DeviceSort),
+    SetPairedSort(PairedSort),
+    SetGroupByType(bool),
+    /// single-line paired device rows, without battery and status details
+    SetCompactList(bool),
+    FilterChanged(String),
+    ToggleFilterFlag(FilterFlags),
+    /// clears the search text and the quick filters
+    ClearFilter,
+    PinInput(bluer::Address, String),
+    /// answer an authorization request, the last flag also allows every future service request from the device
+    AuthorizeService(bluer::Address, bool, bool),
+    SubmitPinCode(bluer::Address, String),
+    /// redraws connect countdowns
+    ConnectTick,
+    SetDiscoverable(cosmic_time::chain::Toggler, bool),
+    /// Enter or Escape pressed while a pairing code is shown, answers the oldest one
+    AnswerConfirmation(bool),
+    SetPairable(cosmic_time::chain::Toggler, bool),
+    DismissWarnings,
+}
+
+/// Formats a theme color for use in an inline svg
+fn hex_color(color: Srgba) -> String {
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        (color.red * 255.0).round() as u8,
+        (color.green * 255.0).round() as u8,
+        (color.blue * 255.0).round() as u8,
+    )
+}
+
+/// Three bar signal icon for an rssi value in dBm
+fn signal_icon(rssi: i16) -> &'static str {
+    match rssi {
+        -60.. => "network-wireless-signal-good-symbolic",
+        -75..=-61 => "network-wireless-signal-ok-symbolic",
+        _ => "network-wireless-signal-weak-symbolic",
+    }
+}
+
+/// Draws recent signal strength samples as a small line graph
+fn signal_sparkline(history: &VecDeque<i16>) -> Element<'static, Message> {
+    const WIDTH: f32 = 120.0;
+    const HEIGHT: f32 = 24.0;
+    // rough range of rssi values seen in practice, in dBm
+    const MIN_RSSI: f32 = -100.0;
+    const MAX_RSSI: f32 = -30.0;
+
+    let hex = hex_color(cosmic::theme::active().cosmic().accent_color());
+    let step = WIDTH / (SIGNAL_HISTORY_LEN - 1) as f32;
+    let points = history
+        .iter()
+        .enumerate()
+        .map(|(i, rssi)| {
+            let level = ((f32::from(*rssi) - MIN_RSSI) / (MAX_RSSI - MIN_RSSI)).clamp(0.0, 1.0);
+            format!(
+                "{:.1},{:.1}",
+                i as f32 * step,
+                HEIGHT - 1.0 - level * (HEIGHT - 2.0)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let data = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}">
+<polyline points="{points}" fill="none" stroke="{hex}" stroke-width="1.5" stroke-linejoin="round"/>
+</svg>"#
+    );
+
+    svg(svg::Handle::from_memory(data.into_bytes()))
+        .width(Length::Fixed(WIDTH))
+        .height(Length::Fixed(HEIGHT))
+        .into()
+}
+
+/// Draws the battery level as a ring filled to `percent`, colored by how low it is
+fn battery_ring(percent: u8, level: BatteryLevel) -> Element<'static, Message> {
+    const SIZE: f32 = 14.0;
+    const RADIUS: f32 = 5.5;
+    let circumference = 2.0 * std::f32::consts::PI * RADIUS;
+    let filled = circumference * f32::from(percent.min(100)) / 100.0;
+
+    let theme = cosmic::theme::active();
+    let cosmic = theme.cosmic();
+    let color = match level {
+        BatteryLevel::Caution => cosmic.destructive_color(),
+        BatteryLevel::Low => cosmic.warning_color(),
+        BatteryLevel::Normal => cosmic.success_color(),
+    };
+    let hex = hex_color(color);
+
+    let data = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{SIZE}" height="{SIZE}" viewBox="0 0 {SIZE} {SIZE}">
+<circle cx="7" cy="7" r="{RADIUS}" fill="none" stroke="{hex}" stroke-opacity="0.25" stroke-width="2"/>
+<circle cx="7" cy="7" r="{RADIUS}" fill="none" stroke="{hex}" stroke-width="2" stroke-linecap="round" stroke-dasharray="{filled} {circumference}" transform="rotate(-90 7 7)"/>
+</svg>"#
+    );
+
+    svg(svg::Handle::from_memory(data.into_bytes()))
+        .width(Length::Fixed(SIZE))
+        .height(Length::Fixed(SIZE))
+        .into()
+}
+
+/// Draws the dot that stands in for the status label in compact rows, there's none while disconnected
+fn status_dot(status: ConnectionStatus) -> Option<Element<'static, Message>> {
+    const SIZE: f32 = 8.0;
+
+    let theme = cosmic::theme::active();
+    let cosmic = theme.cosmic();
+    let color = match status {
+        ConnectionStatus::Connected => cosmic.success_color(),
+        ConnectionStatus::Connecting | ConnectionStatus::Disconnecting => cosmic.warning_color(),
+        ConnectionStatus::Disconnected => return None,
+    };
+    let hex = hex_color(color);
+
+    let data = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{SIZE}" height="{SIZE}" viewBox="0 0 {SIZE} {SIZE}">
+<circle cx="4" cy="4" r="4" fill="{hex}"/>
+</svg>"#
+    );
+
+    Some(
+        svg(svg::Handle::from_memory(data.into_bytes()))
+            .width(Length::Fixed(SIZE))
+            .height(Length::Fixed(SIZE))
+            .into(),
+    )
+}
+
+/// Dims the row of a blocked device. It has to go inside the row's button, which would set its own colors
+fn dim_blocked<'a>(
+    content: impl Into<Element<'a, Message>>,
+    blocked: bool,
+) -> Element<'a, Message> {
+    if !blocked {
+        return content.into();
+    }
+
+    container(content)
+        .class(cosmic::style::Container::Custom(Box::new(|theme| {
+            let mut color: cosmic::iced::Color = theme.cosmic().on_bg_color().into();
+            color.a *= 0.5;
+            cosmic::iced::widget::container::Style {
+                text_color: Some(color),
+                icon_color: Some(color),
+                ..Default::default()
+            }
+        })))
+        .into()
+}
+
+/// "Connected for 1h 23m", or just "Connected" in the first minute
+fn connected_text(since: Option<std::time::Instant>) -> String {
+    let minutes = since.map_or(0, |since| since.elapsed().as_secs() / 60);
+    if minutes == 0 {
+        return fl!("connected");
+    }
+
+    fl!(
+        "connected-for",
+        HashMap::from([("hours", minutes / 60), ("minutes", minutes % 60)])
+    )
+}
+
+fn unix_time_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// "Last connected 5 minutes ago", in the largest unit that fits
+fn last_connected_text(secs_ago: u64) -> String {
+    match secs_ago {
+        0..60 => fl!("last-connected-now"),
+        60..3600 => fl!(
+            "last-connected-minutes",
+            HashMap::from([("count", secs_ago / 60)])
+        ),
+        3600..86400 => fl!(
+            "last-connected-hours",
+            HashMap::from([("count", secs_ago / 3600)])
+        ),
+        _ => fl!(
+            "last-connected-days",
+            HashMap::from([("count", secs_ago / 86400)])
+        ),
+    }
 }
 
 impl CosmicBluetoothApplet {
-    fn handle_worker_event(&mut self, event: WorkerEvent) {
-        match event {
-            WorkerEvent::Ready(tx, e) => {
-                self.worker_tx = Some(tx);
-                self.enabled = e;
+    fn save_config(&self) {
+        if let Some(helper) = self.config_helper.as_ref()
+            && let Err(err) = self.config.write_entry(helper)
+        {
+            tracing::error!(?err, "Error writing config");
+        }
+    }
+
+    fn set_primary_device(&mut self, addr: Option<bluer::Address>) {
+        self.config.primary_device = addr.map(|a| a.to_string());
+        self.save_config();
+    }
+
+    /// Extra actions for a device, shown below its row when expanded
+    fn device_details<'a>(&'a self, dev: &'a BluetoothDevice) -> Element<'a, Message> {
+        let addr = dev.address;
+        let primary = if self.config.primary_device() == Some(dev.address) {
+            menu_button(text::body(fl!("unset-primary"))).on_press(Message::SetPrimaryDevice(None))
+        } else {
+            menu_button(text::body(fl!("set-primary")))
+                .on_press(Message::SetPrimaryDevice(Some(dev.address)))
+        };
+
+        let mut details = column![padded_control(
+            row![
+                text::caption(addr.to_string())
+                    .font(cosmic::font::mono())
+                    .width(Length::Fill),
+                button::icon(icon::from_name("edit-copy-symbolic").size(16))
+                    .icon_size(16)
+                    .tooltip(fl!("copy-address"))
+                    .on_press(Message::CopyAddress(addr)),
+            ]
+            .align_y(Alignment::Center)
+            .spacing(8)
+        )];
+        if !matches!(dev.status, ConnectionStatus::Connected)
+            && let Some(time) = self.config.last_connected(addr)
+        {
+            details = details.push(padded_control(text::caption(last_connected_text(
+                unix_time_secs().saturating_sub(time),
+            ))));
+        }
+        details = details.push(primary);
+
+        match self.renaming.as_ref() {
+            Some((renaming, value)) if *renaming == addr => {
+                details = details.push(padded_control(
+                    column![
+                        text_input::text_input(fl!("rename-placeholder"), value.as_str())
+                            .on_input(Message::RenameInput)
+                            .on_submit(move |alias| Message::SubmitRename(addr, alias)),
+                        row![
+                            button::standard(fl!("cancel")).on_press(Message::CancelRename),
+                            button::suggested(fl!("rename"))
+                                .on_press(Message::SubmitRename(addr, value.clone())),
+                        ]
+                        .spacing(8),
+                    ]
+                    .spacing(8)
+                    .align_x(Alignment::End),
+                ));
             }
-            WorkerEvent::DeviceMap(m) => self.device_map = Some(m),
-            WorkerEvent::Error(err) => {
-                eprintln!("Bluetooth worker failed with error: {}. Exiting...", err);
-                tracing::error!("Bluetooth worker failed with error: {}. Exiting...", err);
-                std::process::exit(1);
+            _ => {
+                details = details.push(
+                    menu_button(text::body(fl!("rename")))
+                        .on_press(Message::StartRename(dev.address)),
+                );
+            }
+        }
+
+        match self.nicknaming.as_ref() {
+            Some((nicknaming, value)) if *nicknaming == addr => {
+                details = details.push(padded_control(
+                    column![
+                        text_input::text_input(dev.name.as_str(), value.as_str())
+                            .on_input(Message::NicknameInput)
+                            .on_submit(move |nickname| Message::SubmitNickname(addr, nickname)),
+                        row![
+                            button::standard(fl!("cancel")).on_press(Message::CancelNickname),
+                            button::suggested(fl!("save"))
+                                .on_press(Message::SubmitNickname(addr, value.clone())),
+                        ]
+                        .spacing(8),
+                    ]
+                    .spacing(8)
+                    .align_x(Alignment::End),
+                ));
             }
-            WorkerEvent::DeviceAdded(device) => {
-                self.device_map
-                    .as_mut()
-                    .map(|d| d.insert(device.address.clone(), device));
+            _ if self.config.nickname(addr).is_some() => {
+                details = details.extend([
+                    menu_button(text::body(fl!("change-nickname")))
+                        .on_press(Message::StartNickname(addr))
+                        .into(),
+                    menu_button(text::body(fl!("clear-nickname")))
+                        .on_press(Message::SubmitNickname(addr, String::new()))
+                        .into(),
+                ]);
             }
-            WorkerEvent::DeviceRemoved(addr) => {
-                tracing::info!("Device removed: {}", addr);
-                self.device_map.as_mut().map(|d| d.remove(&addr));
+            _ => {
+                details = details.push(
+                    menu_button(text::body(fl!("set-nickname")))
+                        .on_press(Message::StartNickname(addr)),
+                );
             }
-            WorkerEvent::Enabled(true) => {
-                self.enabled = true;
+        }
 
-                if self.popup.is_some()
-                    && let Some(tx) = self.worker_tx.as_ref()
-                {
-                    _ = tx.send(WorkerRequest::SetDiscovery(true));
+        details = details.push(padded_control(
+            anim!(
+                TRUSTED,
+                &self.timeline,
+                fl!("trusted"),
+                dev.is_trusted,
+                move |chain, trusted| Message::SetTrusted(chain, addr, trusted),
+            )
+            .text_size(14)
+            .width(Length::Fill),
+        ));
+
+        // a known battery level is already shown in the row
+        if dev.battery_percent.is_none() {
+            let battery = if dev.battery_supported {
+                fl!("battery-not-reported")
+            } else {
+                fl!("battery-unsupported")
+            };
+            details = details.push(padded_control(text::caption(battery).width(Length::Fill)));
+        }
+
+        if dev.signal_history.len() > 1 {
+            details = details.push(padded_control(
+                row![
+                    text::body(fl!("signal-history")).width(Length::Fill),
+                    signal_sparkline(&dev.signal_history),
+                ]
+                .align_y(Alignment::Center),
+            ));
+        }
+
+        // only input devices are useful as wake sources, and only if bluez lets us control it
+        if dev.category == DeviceCategory::Input
+            && let Some(allowed) = dev.wake_allowed
+        {
+            details = details.push(padded_control(
+                anim!(
+                    WAKE_ALLOWED,
+                    &self.timeline,
+                    fl!("wake-allowed"),
+                    allowed,
+                    move |chain, allowed| Message::SetWakeAllowed(chain, addr, allowed),
+                )
+                .text_size(14)
+                .width(Length::Fill),
+            ));
+        }
+
+        if dev.category == DeviceCategory::Audio && dev.supports_headset {
+            details = details.push(padded_control(
+                anim!(
+                    HEADSET_PROFILE,
+                    &self.timeline,
+                    fl!("headset-profile"),
+                    self.config.headset_profile_enabled(addr),
+                    move |chain, enabled| Message::SetHeadsetProfile(chain, addr, enabled),
+                )
+                .text_size(14)
+                .width(Length::Fill),
+            ));
+        }
+
+        // only worth offering when there's a choice, a single profile is what connecting does anyway
+        if dev.is_paired && dev.profiles.len() > 1 {
+            details = details.push(padded_control(text::caption(fl!("connect-profile"))));
+            details = details.extend(dev.profiles.iter().map(|uuid| {
+                menu_button(text::body(service_name(*uuid)))
+                    .on_press(Message::Request(WorkerRequest::ConnectProfile(addr, *uuid)))
+                    .into()
+            }));
+        }
+
+        let info_shown = self.device_info_shown == Some(addr);
+        details = details.push(
+            menu_button(
+                row![
+                    text::body(fl!("device-info")).width(Length::Fill),
+                    icon::from_name(if info_shown {
+                        "go-up-symbolic"
+                    } else {
+                        "go-down-symbolic"
+                    })
+                    .size(16)
+                    .symbolic(true),
+                ]
+                .align_y(Alignment::Center),
+            )
+            .on_press(Message::ToggleDeviceInfo(addr)),
+        );
+        if info_shown {
+            details = details.push(self.device_info(addr));
+        }
+
+        if matches!(dev.status, ConnectionStatus::Connected) {
+            details = details.push(menu_button(text::body(fl!("reconnect"))).on_press(
+                Message::Request(WorkerRequest::ReconnectDevice(dev.address)),
+            ));
+        }
+
+        if self.confirm_forget == Some(dev.address) {
+            details = details.extend([
+                padded_control(
+                    text::body(fl!(
+                        "forget-device-confirm",
+                        HashMap::from([(
+                            "deviceName",
+                            self.config.device_name(dev.address, &dev.name)
+                        )])
+                    ))
+                    .width(Length::Fill),
+                )
+                .into(),
+                padded_control(
+                    row![
+                        button::custom(text::body(fl!("cancel")).center())
+                            .padding([4, 0])
+                            .height(Length::Fixed(28.0))
+                            .width(Length::Fixed(105.0))
+                            .on_press(Message::ConfirmForget(None)),
+                        button::custom(text::body(fl!("forget-device")).center())
+                            .padding([4, 0])
+                            .height(Length::Fixed(28.0))
+                            .width(Length::Fixed(105.0))
+                            .on_press(Message::ForgetDevice(dev.address)),
+                    ]
+                    .spacing(self.core.system_theme().cosmic().space_xxs())
+                    .width(Length::Shrink)
+                    .align_y(Alignment::Center),
+                )
+                .align_x(Alignment::Center)
+                .into(),
+            ]);
+        } else {
+            details = details.push(
+                menu_button(text::body(fl!("forget-device")))
+                    .on_press(Message::ConfirmForget(Some(dev.address))),
+            );
+        }
+
+        details.padding([0, 0, 0, 28]).into()
+    }
+
+    /// The technical details of a device, with the services by name where they're known
+    fn device_info(&self, addr: bluer::Address) -> Element<'static, Message> {
+        let Some(info) = self.device_info.get(&addr) else {
+            return padded_control(text::caption(fl!("loading"))).into();
+        };
+
+        let mut lines = vec![fl!(
+            "address-type",
+            HashMap::from([("type", info.address_type.clone())])
+        )];
+        if let Some(class) = info.class {
+            lines.push(fl!(
+                "device-class",
+                HashMap::from([("class", format!("0x{class:06x}"))])
+            ));
+        }
+        if let Some(appearance) = info.appearance {
+            lines.push(fl!(
+                "device-appearance",
+                HashMap::from([("appearance", format!("0x{appearance:04x}"))])
+            ));
+        }
+        if let Some((vendor, product)) = info.vendor_product {
+            lines.push(fl!(
+                "vendor-product",
+                HashMap::from([
+                    ("vendor", format!("0x{vendor:04x}")),
+                    ("product", format!("0x{product:04x}"))
+                ])
+            ));
+        }
+
+        let mut info_column =
+            Column::with_children(lines.into_iter().map(|line| text::caption(line).into()));
+        if !info.services.is_empty() {
+            info_column =
+                info_column
+                    .push(text::caption(fl!("services")))
+                    .extend(info.services.iter().map(|uuid| {
+                        container(text::caption(service_name(*uuid)))
+                            .padding([0, 0, 0, 12])
+                            .into()
+                    }));
+        }
+
+        padded_control(info_column.spacing(2)).into()
+    }
+
+    /// How many devices are connected right now
+    fn connected_count(&self) -> usize {
+        self.device_map
+            .iter()
+            .flat_map(|d| d.values())
+            .filter(|dev| matches!(dev.status, ConnectionStatus::Connected))
+            .count()
+    }
+
+    /// Whether any device is still connecting or disconnecting
+    fn in_transition(&self) -> bool {
+        self.device_map.iter().flat_map(|d| d.values()).any(|dev| {
+            matches!(
+                dev.status,
+                ConnectionStatus::Connecting | ConnectionStatus::Disconnecting
+            )
+        })
+    }
+
+    /// Text for the panel icon's tooltip
+    fn tooltip(&self) -> String {
+        if self.worker_error.is_some() {
+            return fl!("bluetooth-unavailable");
+        }
+
+        if self.no_adapter {
+            return fl!("no-adapter");
+        }
+
+        if !self.enabled {
+            return fl!("bluetooth-off");
+        }
+
+        if self.config.tooltip == TooltipVerbosity::Status {
+            return fl!("bluetooth-on");
+        }
+
+        let mut connected: Vec<_> = self
+            .device_map
+            .iter()
+            .flat_map(|d| d.values())
+            .filter(|dev| matches!(dev.status, ConnectionStatus::Connected))
+            .collect();
+        connected.sort_by_key(|dev| self.config.device_name(dev.address, &dev.name));
+
+        if connected.is_empty() {
+            return format!("{}\n{}", fl!("bluetooth-on"), fl!("no-devices-connected"));
+        }
+
+        connected
+            .into_iter()
+            .fold(fl!("bluetooth-on"), |mut text, dev| {
+                text.push('\n');
+                match dev.battery_percent {
+                    Some(battery) => text.push_str(&format!(
+                        "{} ({battery}%)",
+                        self.config.device_name(dev.address, &dev.name)
+                    )),
+                    None => text.push_str(self.config.device_name(dev.address, &dev.name)),
                 }
+                text
+            })
+    }
+
+    /// How long notifications are still snoozed for, `None` once the snooze has run out
+    fn snooze_remaining(&self) -> Option<Duration> {
+        self.snoozed_until
+            .map(|until| until.saturating_duration_since(std::time::Instant::now()))
+            .filter(|remaining| !remaining.is_zero())
+    }
+
+    fn battery_indicator(&self, battery: u8) -> Element<'static, Message> {
+        let level = self.config.battery_level(battery);
+        match self.config.battery_display {
+            BatteryDisplay::Icon => {
+                let icon = match level {
+                    BatteryLevel::Caution => "battery-caution",
+                    BatteryLevel::Low => "battery-low",
+                    BatteryLevel::Normal => "battery",
+                };
+                icon::from_name(icon).symbolic(true).size(14).into()
             }
-            WorkerEvent::Enabled(false) => {
-                self.enabled = false;
-            }
-            WorkerEvent::DeviceUpdate(addr, update) => {
-                self.device_map.as_mut().map(|d| {
-                    if let Some(dev) = d.get_mut(&addr) {
-                        dev.handle_device_updates(update);
+            BatteryDisplay::Ring => battery_ring(battery, level),
+        }
+    }
+
+    /// Battery levels shown at the end of a paired device's row
+    fn device_battery(&self, dev: &BluetoothDevice) -> Option<Element<'static, Message>> {
+        if !dev.batteries.is_empty() {
+            let levels = dev
+                .batteries
+                .iter()
+                .enumerate()
+                .map(|(i, (label, battery))| {
+                    let label = if label.is_empty() {
+                        fl!("battery-number", HashMap::from([("number", i + 1)]))
                     } else {
-                        tracing::warn!("Bluetooth worker and app model are out of sync!")
-                    }
+                        label.clone()
+                    };
+                    row!(
+                        text::caption(label),
+                        self.battery_tinted(
+                            row!(
+                                self.battery_indicator(*battery),
+                                text::caption(format!("{battery}%"))
+                            )
+                            .align_y(Alignment::Center)
+                            .spacing(2),
+                            *battery
+                        ),
+                    )
+                    .align_y(Alignment::Center)
+                    .spacing(2)
+                    .into()
                 });
+
+            Some(
+                Column::with_children(levels)
+                    .align_x(Alignment::End)
+                    .width(Length::Shrink)
+                    .into(),
+            )
+        } else if let Some(battery) = dev.battery_percent {
+            let status = row!(
+                self.battery_indicator(battery),
+                text::body(format!("{battery}%"))
+            )
+            .align_y(Alignment::Center)
+            .spacing(2)
+            .width(Length::Shrink);
+
+            let content = container(self.battery_tinted(status, battery))
+                .align_x(Alignment::End)
+                .align_y(Alignment::Center);
+
+            Some(content.into())
+        } else {
+            None
+        }
+    }
+
+    /// Connection status shown at the end of a paired device's row, with a countdown and spinner while connecting
+    fn device_status(&self, dev: &BluetoothDevice) -> Vec<Element<'static, Message>> {
+        let mut status = Vec::new();
+        match dev.status {
+            ConnectionStatus::Connected => {
+                status.push(
+                    text::body(connected_text(dev.connected_since))
+                        .align_x(Alignment::End)
+                        .align_y(Alignment::Center)
+                        .into(),
+                );
             }
-            WorkerEvent::ConnectFailed(addr) => {
-                self.device_map.as_mut().map(|d| {
-                    if let Some(dev) = d.get_mut(&addr) {
-                        dev.status = ConnectionStatus::Disconnected;
-                    } else {
-                        tracing::warn!("Bluetooth worker and app model are out of sync!")
-                    }
-                });
+            ConnectionStatus::Connecting | ConnectionStatus::Disconnecting => {
+                let label = if matches!(dev.status, ConnectionStatus::Connecting) {
+                    fl!("connecting")
+                } else {
+                    fl!("disconnecting")
+                };
+                status.push(
+                    text::body(label)
+                        .align_x(Alignment::End)
+                        .align_y(Alignment::Center)
+                        .into(),
+                );
+
+                if let Some(since) = dev.connecting_since {
+                    let remaining = self
+                        .config
+                        .connect_timeout()
+                        .saturating_sub(since.elapsed());
+                    status.push(
+                        text::caption(fl!(
+                            "connect-timeout-remaining",
+                            HashMap::from([("seconds", remaining.as_secs().to_string())])
+                        ))
+                        .into(),
+                    );
+                }
+
+                // TODO make more consistent with spinning icon on cosmic-greeter?
+                status.push(
+                    icon::from_name("process-working-symbolic")
+                        .size(24)
+                        .symbolic(true)
+                        .into(),
+                );
             }
-            WorkerEvent::ConfirmCode(code, addr) => {
-                self.device_map.as_mut().map(|d| {
-                    if let Some(dev) = d.get_mut(&addr) {
-                        dev.display_code = Some(code)
+            ConnectionStatus::Disconnected => {}
+        }
+        status
+    }
+
+    /// Tints a battery's icon and percentage once it's low. The colors come from the theme, so they keep their
+    /// contrast in light and dark themes
+    fn battery_tinted<'a>(
+        &self,
+        content: impl Into<Element<'a, Message>>,
+        battery: u8,
+    ) -> Element<'a, Message> {
+        let level = self.config.battery_level(battery);
+        container(content)
+            .class(cosmic::style::Container::Custom(Box::new(move |theme| {
+                let cosmic = theme.cosmic();
+                let color = match level {
+                    BatteryLevel::Caution => Some(cosmic.destructive_color().into()),
+                    BatteryLevel::Low => Some(cosmic.warning_color().into()),
+                    BatteryLevel::Normal => None,
+                };
+                cosmic::iced::widget::container::Style {
+                    text_color: color,
+                    icon_color: color,
+                    ..Default::default()
+                }
+            })))
+            .into()
+    }
+
+    /// Explains a failed connect, with the raw error behind an expander
+    fn connect_error<'a>(
+        &self,
+        addr: bluer::Address,
+        err: &'a ConnectError,
+    ) -> Element<'a, Message> {
+        let icon_name = match err.kind {
+            ConnectErrorKind::Authentication => "dialog-password-symbolic",
+            ConnectErrorKind::Timeout => "preferences-system-time-symbolic",
+            ConnectErrorKind::ProfileUnavailable => "action-unavailable-symbolic",
+            ConnectErrorKind::OutOfRange => "network-wireless-signal-none-symbolic",
+            ConnectErrorKind::Busy => "emblem-synchronizing-symbolic",
+            ConnectErrorKind::Other => "dialog-error-symbolic",
+        };
+        let kind = err.kind.as_str();
+        let expanded = self.expanded_error == Some(addr);
+
+        let mut content = column![
+            row![
+                icon::from_name(icon_name).size(16).symbolic(true),
+                text::caption(fl!("connect-error", HashMap::from([("kind", kind)])))
+                    .width(Length::Fill),
+                button::icon(
+                    icon::from_name(if expanded {
+                        "go-up-symbolic"
                     } else {
-                        tracing::warn!("Bluetooth worker and app model are out of sync!")
-                    }
-                });
-            }
+                        "go-down-symbolic"
+                    })
+                    .size(16),
+                )
+                .icon_size(16)
+                .on_press(Message::ToggleErrorDetails(addr)),
+            ]
+            .align_y(Alignment::Center)
+            .spacing(8)
+        ];
+
+        if expanded {
+            content = content.push(text::caption(err.details.as_str()));
         }
+
+        if self
+            .connect_failures
+            .get(&addr)
+            .copied()
+            .unwrap_or_default()
+            >= FAILURES_BEFORE_UNPAIR
+        {
+            content = content.push(
+                button::standard(fl!("unpair-and-retry")).on_press(Message::ForgetDevice(addr)),
+            );
+        }
+
+        padded_control(content).into()
     }
-}
 
-impl cosmic::Application for CosmicBluetoothApplet {
-    type Message = Message;
-    type Executor = cosmic::SingleThreadExecutor;
-    type Flags = ();
-    const APP_ID: &'static str = config::APP_ID;
+    fn authorization_text(&self, addr: bluer::Address, service: Option<bluer::Uuid>) -> String {
+        let device_name = self
+            .device_map
+            .as_ref()
+            .and_then(|d| d.get(&addr))
+            .map_or_else(
+                || addr.to_string(),
+                |dev| self.config.device_name(dev.address, &dev.name).to_string(),
+            );
 
-    fn init(core: cosmic::Core, _flags: Self::Flags) -> (Self, Task<Self::Message>) {
-        (
-            Self {
-                core,
-                ..Default::default()
-            },
-            cosmic::task::none(),
-        )
+        match service {
+            Some(service) => fl!(
+                "authorize-service",
+                HashMap::from([
+                    ("deviceName", device_name),
+                    ("service", service_name(service))
+                ])
+            ),
+            None => fl!(
+                "authorize-pairing",
+                HashMap::from([("deviceName", device_name)])
+            ),
+        }
     }
 
-    fn core(&self) -> &cosmic::Core {
-        &self.core
+    /// The device's icon, or the one the user configured for it. A configured icon missing from the theme falls
+    /// back to the device's own
+    fn device_icon(&self, dev: &BluetoothDevice) -> icon::Named {
+        match self.config.icon_override(dev.address, &dev.name) {
+            Some(name) => icon::from_name(name.to_string())
+                .fallback(Some(icon::IconFallback::Names(vec![dev.icon.into()]))),
+            None => icon::from_name(dev.icon),
+        }
     }
 
-    fn core_mut(&mut self) -> &mut cosmic::Core {
-        &mut self.core
+    /// The worker's recent warnings, with a button to dismiss them all
+    fn warnings_banner(&self) -> Element<'_, Message> {
+        let messages = self
+            .warnings
+            .iter()
+            .fold(column![].spacing(2), |col, (warning, _)| {
+                col.push(text::caption(
+                    warning.lines().next().unwrap_or_default().to_string(),
+                ))
+            });
+
+        padded_control(
+            row![
+                icon::from_name("dialog-warning-symbolic")
+                    .size(16)
+                    .symbolic(true),
+                column![text::body(fl!("warning-title")), messages]
+                    .spacing(4)
+                    .width(Length::Fill),
+                button::icon(icon::from_name("window-close-symbolic").size(16))
+                    .icon_size(16)
+                    .tooltip(fl!("dismiss"))
+                    .on_press(Message::DismissWarnings),
+            ]
+            .spacing(8),
+        )
+        .into()
     }
 
-    fn on_close_requested(&self, id: window::Id) -> Option<Message> {
-        Some(Message::CloseRequested(id))
+    /// A device's row with its details tooltip, followed by its action menu once opened with a right click
+    fn device_row<'a>(
+        &self,
+        content: impl Into<Element<'a, Message>>,
+        dev: &BluetoothDevice,
+    ) -> Element<'a, Message> {
+        let open = self.device_menu == Some(dev.address);
+        let row = mouse_area(self.with_device_tooltip(content, dev))
+            .on_right_press(Message::DeviceMenu((!open).then_some(dev.address)));
+        if !open {
+            return row.into();
+        }
+
+        column![row, self.device_actions(dev)].into()
     }
 
-    fn update(&mut self, message: Self::Message) -> Task<Self::Message> {
-        match message {
-            Message::BluetoothEvent(ev) => self.handle_worker_event(ev),
-            Message::Request(worker_request) => {
-                if let Some(worker_tx) = self.worker_tx.as_mut() {
-                    if let Some(device_map) = self.device_map.as_mut()
-                        && let WorkerRequest::ConnectDevice(addr)
-                        | WorkerRequest::DisconnectDevice(addr) = worker_request
-                    {
-                        if let Some(dev) = device_map.get_mut(&addr) {
-                            match worker_request {
-                                WorkerRequest::ConnectDevice(_) => {
-                                    dev.status = ConnectionStatus::Connecting
-                                }
-                                WorkerRequest::DisconnectDevice(_) => {
-                                    dev.status = ConnectionStatus::Disconnecting
-                                }
-                                _ => {}
-                            }
-                        } else {
-                            tracing::warn!("Bluetooth worker and app model are out of sync!")
-                        }
-                    }
+    /// Everything that can be done with a device, adapted to whether it's paired and connected
+    fn device_actions(&self, dev: &BluetoothDevice) -> Element<'static, Message> {
+        let addr = dev.address;
+        let connect = match dev.status {
+            ConnectionStatus::Connected => {
+                Some((fl!("disconnect"), WorkerRequest::DisconnectDevice(addr)))
+            }
+            ConnectionStatus::Connecting => {
+                Some((fl!("cancel"), WorkerRequest::CancelConnect(addr)))
+            }
+            ConnectionStatus::Disconnected if dev.is_paired => {
+                Some((fl!("connect"), WorkerRequest::ConnectDevice(addr)))
+            }
+            ConnectionStatus::Disconnected => {
+                Some((fl!("pair"), WorkerRequest::ConnectDevice(addr)))
+            }
+            ConnectionStatus::Disconnecting => None,
+        };
 
-                    _ = worker_tx.send(worker_request)
+        let mut menu = column![];
+        if let Some((label, request)) = connect {
+            menu = menu.push(menu_button(text::body(label)).on_press(Message::Request(request)));
+        }
+        if !dev.is_paired && matches!(dev.status, ConnectionStatus::Disconnected) {
+            menu = menu.push(
+                menu_button(text::body(fl!("pair-only")))
+                    .on_press(Message::Request(WorkerRequest::PairDevice(addr))),
+            );
+        }
+        let block = if dev.is_blocked {
+            fl!("unblock")
+        } else {
+            fl!("block")
+        };
+        menu = menu.push(menu_button(text::body(block)).on_press(Message::Request(
+            WorkerRequest::SetBlocked(addr, !dev.is_blocked),
+        )));
+        if dev.is_paired {
+            let trust = if dev.is_trusted {
+                fl!("untrust")
+            } else {
+                fl!("trust")
+            };
+            menu = menu.extend([
+                menu_button(text::body(trust))
+                    .on_press(Message::Request(WorkerRequest::SetTrusted(
+                        addr,
+                        !dev.is_trusted,
+                    )))
+                    .into(),
+                menu_button(text::body(fl!("rename")))
+                    .on_press(Message::StartRename(addr))
+                    .into(),
+                menu_button(text::body(fl!("forget-device")))
+                    .on_press(Message::ConfirmForget(Some(addr)))
+                    .into(),
+            ]);
+        }
+
+        container(menu).padding([0, 0, 0, 28]).into()
+    }
+
+    /// Shows the device's status, battery, address and type when hovering `content`
+    fn with_device_tooltip<'a>(
+        &self,
+        content: impl Into<Element<'a, Message>>,
+        dev: &BluetoothDevice,
+    ) -> Element<'a, Message> {
+        let status = match dev.status {
+            ConnectionStatus::Connected => "connected",
+            ConnectionStatus::Connecting => "connecting",
+            ConnectionStatus::Disconnected => "disconnected",
+            ConnectionStatus::Disconnecting => "disconnecting",
+        };
+        let mut lines = vec![fl!("tooltip-status", HashMap::from([("status", status)]))];
+        if let Some(battery) = dev.battery_percent {
+            lines.push(fl!(
+                "tooltip-battery",
+                HashMap::from([("percent", battery)])
+            ));
+        }
+        lines.push(fl!(
+            "tooltip-address",
+            HashMap::from([("address", dev.address.to_string())])
+        ));
+        lines.push(fl!(
+            "tooltip-type",
+            HashMap::from([(
+                "type",
+                fl!(
+                    "device-category",
+                    HashMap::from([("category", dev.category.as_str())])
+                )
+            )])
+        ));
+
+        tooltip(
+            content,
+            text::caption(lines.join("\n")),
+            tooltip::Position::Top,
+        )
+        .into()
+    }
+
+    /// The paired and nearby devices matching the filter, in the order they're shown
+    fn device_lists(&self) -> (Vec<&BluetoothDevice>, Vec<&BluetoothDevice>) {
+        let filter = self.filter.trim().to_lowercase();
+        let Some(device_map) = self.device_map.as_ref() else {
+            return (Vec::new(), Vec::new());
+        };
+
+        let (mut paired, mut unpaired): (Vec<&BluetoothDevice>, Vec<&BluetoothDevice>) = device_map
+            .values()
+            .filter(|d| self.filter_flags.matches(d))
+            .filter(|d| {
+                d.matches(&filter)
+                    || self
+                        .config
+                        .nickname(d.address)
+                        .is_some_and(|n| n.to_lowercase().contains(&filter))
+            })
+            .filter(|d| d.is_paired || !(self.config.hide_unnamed_devices && d.is_unnamed()))
+            .partition(|d| d.is_paired);
+
+        match self.config.paired_sort {
+            PairedSort::Name => paired.sort_by_key(|f| self.config.device_name(f.address, &f.name)),
+            PairedSort::Recent => paired.sort_by_key(|f| {
+                (
+                    std::cmp::Reverse(self.config.last_connected(f.address)),
+                    self.config.device_name(f.address, &f.name),
+                )
+            }),
+        }
+        match self.config.nearby_sort {
+            DeviceSort::Name => {
+                unpaired.sort_by_key(|f| self.config.device_name(f.address, &f.name))
+            }
+            DeviceSort::Signal => unpaired.sort_by_key(|f| {
+                (
+                    f.is_unnamed(),
+                    f.rssi.is_none(),
+                    f.rssi.map(std::cmp::Reverse),
+                    self.config.device_name(f.address, &f.name),
+                )
+            }),
+        }
+
+        if self.config.group_by_type {
+            // stable, so each group keeps the order above
+            paired.sort_by_key(|f| f.category);
+            unpaired.sort_by_key(|f| f.category);
+        }
+
+        (paired, unpaired)
+    }
+
+    /// What clicking a device's row does
+    fn primary_action(&self, dev: &BluetoothDevice) -> Option<Message> {
+        let request = match dev.status {
+            ConnectionStatus::Connected => WorkerRequest::DisconnectDevice(dev.address),
+            ConnectionStatus::Connecting => WorkerRequest::CancelConnect(dev.address),
+            ConnectionStatus::Disconnecting => return None,
+            // it would only fail, the device menu offers unblocking
+            ConnectionStatus::Disconnected if dev.is_blocked => return None,
+            ConnectionStatus::Disconnected if !dev.is_paired && self.config.confirm_before_pair => {
+                let selected = self.pair_candidate == Some(dev.address);
+                return Some(Message::SelectPairCandidate(
+                    (!selected).then_some(dev.address),
+                ));
+            }
+            ConnectionStatus::Disconnected => WorkerRequest::ConnectDevice(dev.address),
+        };
+        Some(Message::Request(request))
+    }
+
+    /// Moves keyboard focus to the next or previous device row, wrapping around at the ends
+    fn move_focus(&mut self, forward: bool) {
+        let (paired, unpaired) = self.device_lists();
+        let mut rows: Vec<bluer::Address> = paired.iter().map(|dev| dev.address).collect();
+        if self.enabled && self.config.show_visible_devices {
+            rows.extend(unpaired.iter().map(|dev| dev.address));
+        }
+        if rows.is_empty() {
+            self.focused_device = None;
+            return;
+        }
+
+        let current = self
+            .focused_device
+            .and_then(|addr| rows.iter().position(|a| *a == addr));
+        let next = match (current, forward) {
+            (Some(i), true) => (i + 1) % rows.len(),
+            (Some(i), false) => (i + rows.len() - 1) % rows.len(),
+            (None, true) => 0,
+            (None, false) => rows.len() - 1,
+        };
+        self.focused_device = Some(rows[next]);
+    }
+
+    /// Puts a header in front of each group when grouping by type is on, `categories` holds the category of
+    /// each element in order
+    fn group_by_category<'a>(
+        &self,
+        categories: Vec<DeviceCategory>,
+        elements: Vec<Element<'a, Message>>,
+    ) -> Vec<Element<'a, Message>> {
+        if !self.config.group_by_type {
+            return elements;
+        }
+
+        let mut grouped = Vec::with_capacity(elements.len());
+        let mut current = None;
+        for (category, element) in categories.into_iter().zip(elements) {
+            if current != Some(category) {
+                current = Some(category);
+                grouped.push(
+                    padded_control(text::caption_heading(fl!(
+                        "device-category",
+                        HashMap::from([("category", category.as_str())])
+                    )))
+                    .into(),
+                );
+            }
+            grouped.push(element);
+        }
+        grouped
+    }
+
+    /// Forgets everything that belonged to a worker that is gone, no request to it can be answered anymore
+    fn reset_worker_state(&mut self) {
+        self.worker_tx = None;
+        self.device_map = None;
+        self.adapter_info = None;
+        self.reconnecting = false;
+        self.discovering_since = None;
+        self.authorizations.clear();
+        self.pin_entry.clear();
+        self.displayed_passkeys.clear();
+        self.pending_confirmations.clear();
+    }
+
+    /// Takes the pairing code of a device off screen, leaving the other pending confirmations alone
+    fn clear_confirmation(&mut self, addr: bluer::Address) {
+        self.pending_confirmations.retain(|(a, ..)| *a != addr);
+    }
+
+    /// Each pending pairing code with its own confirm and cancel, oldest first. A device that is still being
+    /// fetched is shown by its address
+    fn confirmations(&self) -> Vec<Element<'_, Message>> {
+        let space_xxs = self.core.system_theme().cosmic().space_xxs();
+
+        self.pending_confirmations
+            .iter()
+            .map(|(addr, code, deadline)| {
+                let addr = *addr;
+                let dev = self.device_map.as_ref().and_then(|d| d.get(&addr));
+                let device_name = dev.map_or_else(
+                    || addr.to_string(),
+                    |dev| self.config.device_name(addr, &dev.name).to_string(),
+                );
+                let device_icon = dev.map_or_else(
+                    || icon::from_name(DEFAULT_DEVICE_ICON),
+                    |dev| self.device_icon(dev),
+                );
+                let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+                column![
+                    padded_control(
+                        row![
+                            device_icon.size(16).symbolic(true),
+                            text::body(device_name.clone()).align_x(Alignment::Start),
+                        ]
+                        .align_y(Alignment::Center)
+                        .spacing(12)
+                    ),
+                    padded_control(
+                        text::body(fl!(
+                            "confirm-pin",
+                            HashMap::from([("deviceName", device_name)])
+                        ))
+                        .align_x(Alignment::Start)
+                        .align_y(Alignment::Center)
+                        .width(Length::Fill)
+                    ),
+                    padded_control(text::title3(code).center().width(Length::Fixed(280.0)))
+                        .align_x(Alignment::Center),
+                    padded_control(
+                        text::caption(fl!(
+                            "confirm-remaining",
+                            HashMap::from([("seconds", remaining.as_secs())])
+                        ))
+                        .center()
+                        .width(Length::Fill)
+                    ),
+                    padded_control(
+                        row![
+                            button::custom(text::body(fl!("cancel")).center())
+                                .padding([4, 0])
+                                .height(Length::Fixed(28.0))
+                                .width(Length::Fixed(105.0))
+                                .on_press(Message::ConfirmCode(addr, false)),
+                            button::custom(text::body(fl!("confirm")).center())
+                                .padding([4, 0])
+                                .height(Length::Fixed(28.0))
+                                .width(Length::Fixed(105.0))
+                                .on_press(Message::ConfirmCode(addr, true)),
+                        ]
+                        .spacing(space_xxs)
+                        .width(Length::Shrink)
+                        .align_y(Alignment::Center)
+                    )
+                    .align_x(Alignment::Center)
+                ]
+                .into()
+            })
+            .collect()
+    }
+
+    fn close_pairing_notification(&mut self, addr: bluer::Address) {
+        if let Some(id) = self.pairing_notifications.remove(&addr) {
+            tokio::spawn(async move {
+                if let Err(e) = notification::close(id).await {
+                    tracing::warn!("could not close pairing notification: {e}");
+                }
+            });
+        }
+    }
+
+    /// Notifies about a pairing request while the popup is closed. Without `confirmable` the request needs input
+    /// only the popup can take, so the notification just offers to open it.
+    fn notify_pairing_request(
+        &self,
+        addr: bluer::Address,
+        body: String,
+        confirmable: bool,
+    ) -> Task<Message> {
+        let mut notification = Notification::new(
+            fl!("pairing-request"),
+            body,
+            "cosmic-applet-bluetooth-active-symbolic",
+        );
+        notification.actions = vec![("default", String::new())];
+        if confirmable {
+            notification.actions.push(("confirm", fl!("confirm")));
+        }
+        notification.actions.push(("cancel", fl!("cancel")));
+        notification.urgent = true;
+
+        Task::perform(notification::send(notification), move |res| {
+            if let Err(e) = res.as_ref() {
+                tracing::warn!("could not show pairing notification: {e}");
+            }
+            cosmic::Action::App(Message::PairingNotified(addr, res.ok()))
+        })
+    }
+
+    /// Animates the bluetooth toggler to a power state reported by bluez, unless it's already headed there
+    /// because the user flipped it
+    fn animate_toggle(&mut self, enabled: bool) {
+        if self.toggle_target == enabled {
+            return;
+        }
+
+        self.toggle_target = enabled;
+        let chain = if enabled {
+            cosmic_time::chain::Toggler::on(BLUETOOTH_ENABLED.clone(), 1.)
+        } else {
+            cosmic_time::chain::Toggler::off(BLUETOOTH_ENABLED.clone(), 1.)
+        };
+        self.timeline.set_chain(chain).start();
+    }
+
+    /// Called when the worker and app model disagree about which devices exist, asks the worker for the device
+    /// the app doesn't know. A device that is still unknown after being fetched once falls back to a full refresh,
+    /// which is debounced, so a device that doesn't exist can't keep this going
+    fn out_of_sync(&mut self, addr: bluer::Address) {
+        self.desync_count += 1;
+        tracing::warn!(
+            "Bluetooth worker and app model are out of sync for {addr}! ({} times so far)",
+            self.desync_count
+        );
+
+        if self
+            .fetched_devices
+            .get(&addr)
+            .is_some_and(|t| t.elapsed() < RESYNC_DEBOUNCE)
+        {
+            self.request_resync();
+        } else if let Some(tx) = self.worker_tx.as_ref() {
+            self.fetched_devices.insert(addr, std::time::Instant::now());
+            _ = tx.send(WorkerRequest::FetchDevice(addr));
+        }
+    }
+
+    /// Asks the worker to rebuild the device list, at most once per [`RESYNC_DEBOUNCE`]
+    fn request_resync(&mut self) {
+        if self
+            .last_resync
+            .is_some_and(|t| t.elapsed() < RESYNC_DEBOUNCE)
+        {
+            return;
+        }
+
+        if let Some(tx) = self.worker_tx.as_ref() {
+            self.last_resync = Some(std::time::Instant::now());
+            _ = tx.send(WorkerRequest::RefreshDevices);
+        }
+    }
+
+    fn handle_worker_event(&mut self, event: WorkerEvent) -> Task<Message> {
+        match event {
+            WorkerEvent::Ready(tx, e) => {
+                self.worker_tx = Some(tx);
+                self.worker_error = None;
+                self.no_adapter = false;
+                self.enabled = e;
+                self.toggle_target = e;
+                self.power_pending_since = None;
+            }
+            WorkerEvent::DeviceMap(mut m) => {
+                for (addr, dev) in &mut m {
+                    if let Some(old) = self.device_map.as_ref().and_then(|d| d.get(addr)) {
+                        dev.keep_app_state(old);
+                    }
+                }
+                self.device_map = Some(m);
+                self.reconnecting = false;
+                self.refreshing = false;
+            }
+            WorkerEvent::Reconnecting => self.reconnecting = true,
+            WorkerEvent::HardBlocked(blocked) => self.hard_blocked = blocked,
+            WorkerEvent::SoftBlocked(blocked) => {
+                self.soft_blocked = blocked;
+                // the toggle, the system settings or rfkill lifted the block, which ends airplane mode too
+                if !blocked && self.config.airplane_mode {
+                    self.config.airplane_mode = false;
+                    self.save_config();
+                }
+            }
+            WorkerEvent::DiscoveryTimedOut => self.scan_timed_out = true,
+            WorkerEvent::Discovering(discovering) => {
+                if !discovering {
+                    self.discovering_since = None;
+                } else if self.discovering_since.is_none() {
+                    self.discovering_since = Some(std::time::Instant::now());
+                }
+            }
+            WorkerEvent::PowerFailed => {
+                self.power_pending_since = None;
+                self.animate_toggle(self.enabled);
+            }
+            WorkerEvent::RefreshFailed => self.refreshing = false,
+            WorkerEvent::Warning(warning) => {
+                if self.warnings.len() == MAX_WARNINGS {
+                    self.warnings.pop_front();
+                }
+                self.warnings
+                    .push_back((warning, std::time::Instant::now()));
+            }
+            WorkerEvent::Pairable(pairable) => {
+                if pairable != self.pairable {
+                    let chain = if pairable {
+                        cosmic_time::chain::Toggler::on(PAIRABLE.clone(), 1.)
+                    } else {
+                        cosmic_time::chain::Toggler::off(PAIRABLE.clone(), 1.)
+                    };
+                    self.timeline.set_chain(chain).start();
+                }
+                self.pairable = pairable;
+            }
+            WorkerEvent::Discoverable(discoverable, timeout) => {
+                if discoverable != self.discoverable {
+                    let chain = if discoverable {
+                        cosmic_time::chain::Toggler::on(DISCOVERABLE.clone(), 1.)
+                    } else {
+                        cosmic_time::chain::Toggler::off(DISCOVERABLE.clone(), 1.)
+                    };
+                    self.timeline.set_chain(chain).start();
+                }
+                self.discoverable = discoverable;
+                self.discoverable_until = timeout
+                    .filter(|_| discoverable)
+                    .map(|timeout| std::time::Instant::now() + timeout);
+            }
+            WorkerEvent::NoAdapter => {
+                self.reset_worker_state();
+                self.adapters.clear();
+                self.enabled = false;
+                self.toggle_target = false;
+                self.no_adapter = true;
+            }
+            WorkerEvent::AdapterInfo(info) => self.adapter_info = Some(info),
+            WorkerEvent::Adapters(adapters) => self.adapters = adapters,
+            WorkerEvent::Error(err) => {
+                tracing::error!("Bluetooth worker failed with error: {}. Restarting...", err);
+                self.reset_worker_state();
+                self.worker_error = Some(err);
+            }
+            WorkerEvent::DeviceAdded(mut device) => {
+                self.fetched_devices.remove(&device.address);
+                // devices arrive one by one before the first full map while the worker starts, and again on every
+                // refresh, when they replace what the applet already knows
+                let device_map = self.device_map.get_or_insert_with(HashMap::new);
+                if let Some(old) = device_map.get(&device.address) {
+                    device.keep_app_state(old);
+                }
+                device_map.insert(device.address, device);
+            }
+            WorkerEvent::DeviceDetails(addr, info) => {
+                self.device_info.insert(addr, info);
+            }
+            WorkerEvent::DeviceRemoved(addr) => {
+                self.device_info.remove(&addr);
+                tracing::info!("Device removed: {}", addr);
+                self.device_map.as_mut().map(|d| d.remove(&addr));
+                if self.expanded_device == Some(addr) {
+                    self.expanded_device = None;
+                }
+
+                if self.config.primary_device() == Some(addr) {
+                    self.set_primary_device(None);
+                }
+            }
+            WorkerEvent::Enabled(true) => {
+                self.enabled = true;
+                self.power_pending_since = None;
+                self.animate_toggle(true);
+
+                if self.popup.is_some()
+                    && let Some(tx) = self.worker_tx.as_ref()
+                {
+                    _ = tx.send(WorkerRequest::SetDiscovery(true));
+                }
+            }
+            WorkerEvent::Enabled(false) => {
+                self.enabled = false;
+                self.power_pending_since = None;
+                self.animate_toggle(false);
+            }
+            WorkerEvent::DeviceUpdate(addr, update) => {
+                if matches!(update, DeviceUpdate::Paired(true)) {
+                    self.displayed_passkeys.remove(&addr);
+                    self.clear_confirmation(addr);
+                    self.close_pairing_notification(addr);
+                }
+
+                let Some(dev) = self.device_map.as_mut().and_then(|d| d.get_mut(&addr)) else {
+                    self.out_of_sync(addr);
+                    return Task::none();
+                };
+
+                if matches!(update, DeviceUpdate::Connected(true)) {
+                    self.connect_failures.remove(&addr);
+                }
+
+                // bluez repeats properties it already reported, only a change of status is worth a notification
+                let transition = dev.is_connection_change(&update);
+                let connected = transition && matches!(update, DeviceUpdate::Connected(true));
+                let battery = match update {
+                    DeviceUpdate::Battery(battery) => Some(battery),
+                    _ => None,
+                };
+                dev.handle_device_updates(update);
+
+                // once when the battery drops into the caution range, again only after it has recovered
+                let low_battery = battery.and_then(|battery| {
+                    if self.config.battery_level(battery) != BatteryLevel::Caution {
+                        self.low_battery_notified.remove(&addr);
+                        return None;
+                    }
+
+                    (!self.low_battery_notified.contains(&addr)).then(|| {
+                        Notification::new(
+                            fl!(
+                                "battery-low",
+                                HashMap::from([(
+                                    "deviceName",
+                                    self.config.device_name(dev.address, &dev.name)
+                                )])
+                            ),
+                            fl!("battery-remaining", HashMap::from([("percent", battery)])),
+                            "battery-caution-symbolic",
+                        )
+                    })
+                });
+
+                let notification = transition.then(|| {
+                    let summary = if matches!(dev.status, ConnectionStatus::Connected) {
+                        fl!("device-connected")
+                    } else {
+                        fl!("device-disconnected")
+                    };
+                    Notification::new(
+                        summary,
+                        self.config.device_name(dev.address, &dev.name).to_string(),
+                        self.config.device_icon(dev.address, &dev.name, dev.icon),
+                    )
+                });
+
+                if connected {
+                    self.config.set_last_connected(addr, unix_time_secs());
+                    self.save_config();
+                }
+
+                if let Some(notification) = notification
+                    && self.config.connection_notifications
+                    && self.snooze_remaining().is_none()
+                {
+                    return Task::perform(notification::send(notification), |res| {
+                        if let Err(e) = res {
+                            tracing::warn!("could not show connection notification: {e}");
+                        }
+                        cosmic::Action::None
+                    });
+                }
+
+                // a warning held back by the snooze comes with the next reading after it
+                if let Some(notification) = low_battery
+                    && self.snooze_remaining().is_none()
+                {
+                    self.low_battery_notified.insert(addr);
+                    return Task::perform(notification::send(notification), |res| {
+                        if let Err(e) = res {
+                            tracing::warn!("could not show low battery notification: {e}");
+                        }
+                        cosmic::Action::None
+                    });
+                }
+            }
+            WorkerEvent::ConnectFailed(addr, err) => {
+                self.displayed_passkeys.remove(&addr);
+                let Some(dev) = self.device_map.as_mut().and_then(|d| d.get_mut(&addr)) else {
+                    self.out_of_sync(addr);
+                    return Task::none();
+                };
+
+                dev.status = ConnectionStatus::Disconnected;
+                dev.connecting_since = None;
+                dev.connect_error = err;
+                // a cancelled connect says nothing about the device
+                if dev.connect_error.is_some() {
+                    *self.connect_failures.entry(addr).or_default() += 1;
+                }
+
+                // the popup shows the error on the device row, otherwise the failure would go unnoticed
+                let notification = dev.connect_error.as_ref().map(|err| {
+                    Notification::new(
+                        fl!(
+                            "connect-failed",
+                            HashMap::from([(
+                                "deviceName",
+                                self.config.device_name(dev.address, &dev.name)
+                            )])
+                        ),
+                        fl!(
+                            "connect-error",
+                            HashMap::from([("kind", err.kind.as_str())])
+                        ),
+                        "dialog-error-symbolic",
+                    )
+                });
+
+                if let Some(notification) = notification
+                    && self.popup.is_none()
+                    && self.snooze_remaining().is_none()
+                {
+                    return Task::perform(notification::send(notification), |res| {
+                        if let Err(e) = res {
+                            tracing::warn!("could not show connect failure notification: {e}");
+                        }
+                        cosmic::Action::None
+                    });
+                }
+            }
+            WorkerEvent::ConfirmCode(code, addr) => {
+                let mut device_name = addr.to_string();
+                if let Some(dev) = self.device_map.as_ref().and_then(|d| d.get(&addr)) {
+                    device_name = self.config.device_name(dev.address, &dev.name).to_string();
+                } else {
+                    self.out_of_sync(addr);
+                }
+                // a new code for the same pairing keeps its place and deadline
+                match self
+                    .pending_confirmations
+                    .iter_mut()
+                    .find(|(a, ..)| *a == addr)
+                {
+                    Some((_, c, _)) => *c = code.clone(),
+                    None => {
+                        let deadline = std::time::Instant::now() + worker::CONFIRMATION_TIMEOUT;
+                        self.pending_confirmations
+                            .push((addr, code.clone(), deadline));
+                    }
+                }
+
+                if self.popup.is_none() {
+                    let body = fl!("confirm-pin", HashMap::from([("deviceName", device_name)]));
+                    return self.notify_pairing_request(addr, format!("{body}\n{code}"), true);
+                }
+            }
+            WorkerEvent::ConfirmationExpired(addr) => {
+                self.clear_confirmation(addr);
+                self.close_pairing_notification(addr);
+            }
+            WorkerEvent::DisplayPasskey(addr, code, entered) => {
+                let first = self
+                    .displayed_passkeys
+                    .insert(addr, (code.clone(), entered))
+                    .is_none();
+
+                // displays repeat as digits are typed, only the first one is worth a notification
+                if first && self.popup.is_none() {
+                    let device_name = self
+                        .device_map
+                        .as_ref()
+                        .and_then(|d| d.get(&addr))
+                        .map_or_else(
+                            || addr.to_string(),
+                            |dev| self.config.device_name(dev.address, &dev.name).to_string(),
+                        );
+                    let body = fl!("type-passkey", HashMap::from([("deviceName", device_name)]));
+                    return self.notify_pairing_request(addr, format!("{body}\n{code}"), false);
+                }
+            }
+            WorkerEvent::AuthorizeService(addr, service) => {
+                // a device asking again keeps its place
+                match self.authorizations.iter_mut().find(|(a, _)| *a == addr) {
+                    Some((_, s)) => *s = service,
+                    None => self.authorizations.push((addr, service)),
+                }
+
+                if self.popup.is_none() {
+                    let body = self.authorization_text(addr, service);
+                    return self.notify_pairing_request(addr, body, false);
+                }
+            }
+            WorkerEvent::RequestPinCode(addr) => {
+                let device_name = match self.device_map.as_ref().and_then(|d| d.get(&addr)) {
+                    Some(dev) => self.config.device_name(dev.address, &dev.name).to_string(),
+                    None => {
+                        self.out_of_sync(addr);
+                        addr.to_string()
+                    }
+                };
+                self.pin_entry.insert(addr, String::new());
+
+                if self.popup.is_none() {
+                    let body = fl!("enter-pin", HashMap::from([("deviceName", device_name)]));
+                    return self.notify_pairing_request(addr, body, false);
+                }
+            }
+        }
+
+        Task::none()
+    }
+}
+
+impl cosmic::Application for CosmicBluetoothApplet {
+    type Message = Message;
+    type Executor = cosmic::SingleThreadExecutor;
+    type Flags = ();
+    const APP_ID: &'static str = config::APP_ID;
+
+    fn init(core: cosmic::Core, _flags: Self::Flags) -> (Self, Task<Self::Message>) {
+        let config_helper =
+            cosmic_config::Config::new(Self::APP_ID, BluetoothAppletConfig::VERSION).ok();
+
+        let config = config_helper
+            .as_ref()
+            .map(|helper| {
+                BluetoothAppletConfig::get_entry(helper).unwrap_or_else(|(errors, config)| {
+                    for err in errors {
+                        tracing::error!(?err, "Error loading config");
+                    }
+                    config
+                })
+            })
+            .unwrap_or_default();
+
+        (
+            Self {
+                core,
+                config,
+                config_helper,
+                ..Default::default()
+            },
+            cosmic::task::none(),
+        )
+    }
+
+    fn core(&self) -> &cosmic::Core {
+        &self.core
+    }
+
+    fn core_mut(&mut self) -> &mut cosmic::Core {
+        &mut self.core
+    }
+
+    fn on_close_requested(&self, id: window::Id) -> Option<Message> {
+        Some(Message::CloseRequested(id))
+    }
+
+    fn update(&mut self, message: Self::Message) -> Task<Self::Message> {
+        match message {
+            Message::BluetoothEvent(ev) => {
+                // a device list that is rebuilt, or whose place in the popup moved, loses its scroll position.
+                // Other updates leave it alone, restoring then would fight the user's own scrolling
+                let rebuilt = matches!(
+                    ev,
+                    WorkerEvent::DeviceMap(_)
+                        | WorkerEvent::DeviceAdded(_)
+                        | WorkerEvent::DeviceRemoved(_)
+                        | WorkerEvent::DeviceUpdate(_, DeviceUpdate::Paired(_))
+                );
+                let task = self.handle_worker_event(ev);
+                if !rebuilt {
+                    return task;
+                }
+
+                let restore = Task::batch(
+                    self.scroll_offsets
+                        .iter()
+                        .map(|(id, offset)| scrollable::scroll_to(id.clone(), *offset)),
+                );
+                return task.chain(restore);
+            }
+            Message::Scrolled(id, offset) => {
+                self.scroll_offsets.insert(id, offset);
+            }
+            Message::Request(worker_request) => {
+                self.device_menu = None;
+                if self.worker_tx.is_none() {
+                    return Task::none();
+                }
+
+                if let WorkerRequest::SelectAdapter(name) = &worker_request {
+                    self.config.selected_adapter = Some(name.clone());
+                    self.save_config();
+                }
+
+                if let WorkerRequest::ConnectDevice(addr) | WorkerRequest::PairDevice(addr) =
+                    worker_request
+                    && self.pair_candidate == Some(addr)
+                {
+                    self.pair_candidate = None;
+                }
+
+                if let Some(device_map) = self.device_map.as_mut()
+                    && let WorkerRequest::ConnectDevice(addr)
+                    | WorkerRequest::DisconnectDevice(addr)
+                    | WorkerRequest::ReconnectDevice(addr) = worker_request
+                {
+                    if let Some(dev) = device_map.get_mut(&addr) {
+                        match worker_request {
+                            WorkerRequest::ConnectDevice(_) | WorkerRequest::ReconnectDevice(_) => {
+                                dev.status = ConnectionStatus::Connecting;
+                                dev.connecting_since = Some(std::time::Instant::now());
+                                dev.connect_error = None;
+                            }
+                            WorkerRequest::DisconnectDevice(_) => {
+                                dev.status = ConnectionStatus::Disconnecting;
+                                dev.connecting_since = None;
+                            }
+                            _ => {}
+                        }
+                    } else {
+                        self.out_of_sync(addr);
+                    }
+                }
+
+                if let WorkerRequest::SetDiscovery(_) = worker_request {
+                    self.scan_timed_out = false;
+                }
+
+                if let WorkerRequest::DisconnectAll = worker_request {
+                    for dev in self.device_map.iter_mut().flat_map(|d| d.values_mut()) {
+                        if matches!(dev.status, ConnectionStatus::Connected) {
+                            dev.status = ConnectionStatus::Disconnecting;
+                        }
+                    }
+                }
+
+                if let Some(worker_tx) = self.worker_tx.as_ref() {
+                    _ = worker_tx.send(worker_request)
+                }
+            }
+            Message::TogglePopup => {
+                let task = if let Some(p) = self.popup.take() {
+                    popup::destroy_popup(p)
+                } else {
+                    // TODO request update of state maybe
+                    let new_id = window::Id::unique();
+                    self.popup.replace(new_id);
+                    self.timeline = Timeline::new();
+                    self.scroll_offsets.clear();
+                    self.toggle_target = self.enabled;
+
+                    // the positioner's anchor and gravity come from the panel anchor, so the popup
+                    // opens toward the screen interior whichever edge the panel is on
+                    let mut popup_settings = self.core.applet.get_popup_settings(
+                        self.core.main_window_id().unwrap(),
+                        new_id,
+                        None,
+                        None,
+                        None,
+                    );
+                    let width = self.config.popup_width();
+                    let height = self.config.popup_height();
+                    if width.is_some() || height.is_some() {
+                        let max_height = self.output_height.unwrap_or(f32::INFINITY);
+                        let mut limits = Limits::NONE.min_height(1.).max_height(max_height);
+                        if let Some(width) = width {
+                            limits = limits.min_width(width).max_width(width);
+                        }
+                        if let Some(height) = height.map(|h| h.min(max_height)) {
+                            limits = limits.min_height(height).max_height(height);
+                        }
+                        popup_settings.positioner.size_limits = limits;
+                    }
+
+                    popup::get_popup(popup_settings)
+                };
+
+                let popup_open = self.popup.is_some();
+                self.scan_timed_out = false;
+                if let Some(worker_tx) = self.worker_tx.as_ref() {
+                    _ = worker_tx.send(WorkerRequest::SetDiscovery(popup_open));
+                }
+
+                return task;
+            }
+            Message::OpenSettings => {
+                let exec = self.config.settings_command().to_string();
+                if let Some(tx) = self.token_tx.as_ref() {
+                    let _ = tx.send(subscription::TokenRequest {
+                        app_id: Self::APP_ID.to_string(),
+                        exec,
+                    });
+                }
+            }
+            Message::Token(u) => match u {
+                TokenUpdate::Init(tx) => {
+                    self.token_tx = Some(tx);
+                }
+                TokenUpdate::Finished => {
+                    self.token_tx = None;
+                }
+                TokenUpdate::ActivationToken { token, .. } => {
+                    let mut args = self.config.settings_command().split_whitespace();
+                    if let Some(program) = args.next() {
+                        let mut cmd = std::process::Command::new(program);
+                        cmd.args(args);
+                        if let Some(token) = token {
+                            cmd.env("XDG_ACTIVATION_TOKEN", &token);
+                            cmd.env("DESKTOP_STARTUP_ID", &token);
+                        }
+                        tokio::spawn(cosmic::process::spawn(cmd));
+                    }
+                }
+            },
+            Message::Frame(instant) => self.timeline.now(instant),
+            Message::ToggleBluetooth(chain, enabled) => {
+                // a second click before the adapter answered would only bounce the toggle back
+                if self.power_pending_since.is_some() {
+                    return Task::none();
+                }
+
+                self.timeline.set_chain(chain).start();
+                self.toggle_target = enabled;
+                if let Some(tx) = self.worker_tx.as_mut() {
+                    self.power_pending_since = Some(std::time::Instant::now());
+                    _ = tx.send(WorkerRequest::SetEnabled(enabled));
+                }
+            }
+            Message::ToggleBluetoothQuick => {
+                // the radio can't be turned on while it's blocked, and the popup explains why
+                if self.hard_blocked {
+                    return Task::none();
+                }
+
+                if self.power_pending_since.is_some() {
+                    return Task::none();
+                }
+
+                let enabled = !self.toggle_target;
+                self.animate_toggle(enabled);
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    self.power_pending_since = Some(std::time::Instant::now());
+                    _ = tx.send(WorkerRequest::SetEnabled(enabled));
+                }
+            }
+            Message::SetAirplane(airplane) => {
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    self.config.airplane_mode = airplane;
+                    self.save_config();
+                    _ = tx.send(WorkerRequest::SetAirplane(airplane));
+                }
+            }
+            Message::ToggleVisibleDevices(enabled) => {
+                self.config.show_visible_devices = enabled;
+                self.save_config();
+            }
+            Message::CloseRequested(_id) => {
+                self.popup = None;
+                self.scroll_offsets.clear();
+                self.device_menu = None;
+                self.focused_device = None;
+                if let Some(worker_tx) = self.worker_tx.as_ref() {
+                    _ = worker_tx.send(WorkerRequest::SetDiscovery(false));
+                }
+            }
+            Message::ConfirmCode(addr, confirm) => {
+                if let Some(worker_tx) = self.worker_tx.as_ref() {
+                    _ = worker_tx.send(WorkerRequest::ConfirmCode(addr, confirm));
+                }
+                if !confirm {
+                    self.pin_entry.remove(&addr);
+                }
+                self.clear_confirmation(addr);
+
+                self.close_pairing_notification(addr);
+            }
+            Message::PairingNotified(addr, id) => {
+                if let Some(id) = id {
+                    self.pairing_notifications.insert(addr, id);
+                }
+            }
+            Message::NotificationAction(id, key) => {
+                let Some(addr) = self
+                    .pairing_notifications
+                    .iter()
+                    .find_map(|(addr, n)| (*n == id).then_some(*addr))
+                else {
+                    return Task::none();
+                };
+
+                match key.as_str() {
+                    "confirm" => return self.update(Message::ConfirmCode(addr, true)),
+                    "cancel" => return self.update(Message::ConfirmCode(addr, false)),
+                    _ if self.popup.is_none() => return self.update(Message::TogglePopup),
+                    _ => {}
+                }
+            }
+            Message::ConfigChanged(config) => {
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    _ = tx.send(WorkerRequest::SetConfig(config.clone()));
+                }
+                self.config = config;
+            }
+            Message::ToggleDeviceDetails(addr) => {
+                if self.expanded_device == Some(addr) {
+                    self.expanded_device = None;
+                } else {
+                    self.expanded_device = Some(addr);
+
+                    // the toggler ids are shared between devices, start them from this device's state
+                    let dev = self.device_map.as_ref().and_then(|d| d.get(&addr));
+                    let wake_allowed = dev.and_then(|dev| dev.wake_allowed);
+                    let trusted = dev.is_some_and(|dev| dev.is_trusted);
+                    let headset = self.config.headset_profile_enabled(addr);
+                    for (id, on) in [
+                        (&WAKE_ALLOWED, wake_allowed.unwrap_or_default()),
+                        (&HEADSET_PROFILE, headset),
+                        (&TRUSTED, trusted),
+                    ] {
+                        let chain = if on {
+                            cosmic_time::chain::Toggler::on((*id).clone(), 1.)
+                        } else {
+                            cosmic_time::chain::Toggler::off((*id).clone(), 1.)
+                        };
+                        self.timeline.set_chain(chain).start();
+                    }
+                }
+            }
+            Message::ToggleDeviceInfo(addr) => {
+                if self.device_info_shown == Some(addr) {
+                    self.device_info_shown = None;
+                } else if let Some(tx) = self.worker_tx.as_ref() {
+                    self.device_info_shown = Some(addr);
+                    _ = tx.send(WorkerRequest::FetchDetails(addr));
+                }
+            }
+            Message::ConnectTick => {
+                if self
+                    .power_pending_since
+                    .is_some_and(|since| since.elapsed() >= POWER_TIMEOUT)
+                {
+                    tracing::warn!("bluetooth didn't turn on or off in time");
+                    self.power_pending_since = None;
+                    self.animate_toggle(self.enabled);
+                }
+                self.warnings
+                    .retain(|(_, at)| at.elapsed() < WARNING_TIMEOUT);
+            }
+            Message::DismissWarnings => self.warnings.clear(),
+            Message::RefreshDevices => {
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    self.refreshing = true;
+                    self.last_resync = Some(std::time::Instant::now());
+                    _ = tx.send(WorkerRequest::RefreshDevices);
+                }
+            }
+            Message::AnswerConfirmation(confirm) => {
+                if let Some((addr, ..)) = self.pending_confirmations.first() {
+                    return self.update(Message::ConfirmCode(*addr, confirm));
+                }
+            }
+            Message::SetPairable(chain, pairable) => {
+                self.timeline.set_chain(chain).start();
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    _ = tx.send(WorkerRequest::SetPairable(pairable));
+                }
+            }
+            Message::SetDiscoverable(chain, discoverable) => {
+                self.timeline.set_chain(chain).start();
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    _ = tx.send(WorkerRequest::SetDiscoverable(discoverable));
+                }
+            }
+            Message::StartRename(addr) => {
+                self.device_menu = None;
+                // the name is typed in the device's details
+                if self.expanded_device != Some(addr) {
+                    _ = self.update(Message::ToggleDeviceDetails(addr));
+                }
+                let name = self
+                    .device_map
+                    .as_ref()
+                    .and_then(|d| d.get(&addr))
+                    .map(|dev| dev.name.clone())
+                    .unwrap_or_default();
+                self.renaming = Some((addr, name));
+            }
+            Message::RenameInput(name) => {
+                if let Some((_, value)) = self.renaming.as_mut() {
+                    *value = name;
+                }
+            }
+            Message::SubmitRename(addr, alias) => {
+                self.renaming = None;
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    _ = tx.send(WorkerRequest::SetAlias(addr, alias.trim().to_string()));
+                }
+            }
+            Message::CancelRename => self.renaming = None,
+            Message::StartNickname(addr) => {
+                let nickname = self.config.nickname(addr).unwrap_or_default().to_string();
+                self.nicknaming = Some((addr, nickname));
+            }
+            Message::NicknameInput(nickname) => {
+                if let Some((_, value)) = self.nicknaming.as_mut() {
+                    *value = nickname;
+                }
+            }
+            Message::SubmitNickname(addr, nickname) => {
+                self.nicknaming = None;
+                // only this applet shows the nickname, bluez and other apps keep the device's own name
+                self.config.set_nickname(addr, nickname.trim());
+                self.save_config();
+            }
+            Message::CancelNickname => self.nicknaming = None,
+            Message::AdapterAliasInput(alias) => self.adapter_alias = Some(alias),
+            Message::SubmitAdapterAlias(alias) => {
+                self.adapter_alias = None;
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    _ = tx.send(WorkerRequest::SetAdapterAlias(alias.trim().to_string()));
+                }
+            }
+            Message::SelectPairCandidate(addr) => self.pair_candidate = addr,
+            Message::FilterChanged(filter) => self.filter = filter,
+            Message::ToggleFilterFlag(flag) => self.filter_flags.toggle(flag),
+            Message::ClearFilter => {
+                self.filter.clear();
+                self.filter_flags = FilterFlags::default();
+            }
+            Message::AuthorizeService(addr, allow, always) => {
+                self.authorizations.retain(|(a, _)| *a != addr);
+                if always && !self.config.always_allow_services(addr) {
+                    self.config.always_allow_services.push(addr.to_string());
+                    self.save_config();
+                }
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    _ = tx.send(WorkerRequest::AuthorizeService(addr, allow));
                 }
+                self.close_pairing_notification(addr);
             }
-            Message::TogglePopup => {
-                let task = if let Some(p) = self.popup.take() {
-                    popup::destroy_popup(p)
-                } else {
-                    // TODO request update of state maybe
-                    let new_id = window::Id::unique();
-                    self.popup.replace(new_id);
-                    self.timeline = Timeline::new();
-
-                    let popup_settings = self.core.applet.get_popup_settings(
-                        self.core.main_window_id().unwrap(),
-                        new_id,
-                        None,
-                        None,
-                        None,
-                    );
-
-                    popup::get_popup(popup_settings)
-                };
-
-                let popup_open = self.popup.is_some();
-                if let Some(worker_tx) = self.worker_tx.as_ref() {
-                    _ = worker_tx.send(WorkerRequest::SetDiscovery(popup_open));
+            Message::PinInput(addr, pin) => {
+                if let Some(value) = self.pin_entry.get_mut(&addr) {
+                    *value = pin;
                 }
-
-                return task;
             }
-            Message::OpenSettings => {
-                let exec = "cosmic-settings bluetooth".to_string();
-                if let Some(tx) = self.token_tx.as_ref() {
-                    let _ = tx.send(subscription::TokenRequest {
-                        app_id: Self::APP_ID.to_string(),
-                        exec,
-                    });
+            Message::SubmitPinCode(addr, pin) => {
+                self.pin_entry.remove(&addr);
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    _ = tx.send(WorkerRequest::SubmitPinCode(addr, pin));
                 }
+                self.close_pairing_notification(addr);
             }
-            Message::Token(u) => match u {
-                TokenUpdate::Init(tx) => {
-                    self.token_tx = Some(tx);
+            Message::SetGroupByType(group) => {
+                self.config.group_by_type = group;
+                self.save_config();
+            }
+            Message::SetCompactList(compact) => {
+                self.config.compact_list = compact;
+                self.save_config();
+            }
+            Message::SetNearbySort(sort) => {
+                self.config.nearby_sort = sort;
+                self.save_config();
+            }
+            Message::SetPairedSort(sort) => {
+                self.config.paired_sort = sort;
+                self.save_config();
+            }
+            Message::WindowFocused(id) => {
+                if self.popup == Some(id) {
+                    self.request_resync();
                 }
-                TokenUpdate::Finished => {
-                    self.token_tx = None;
+            }
+            Message::OutputHeight(name, height) => {
+                if name.as_deref() == Some(self.core.applet.output_name.as_str()) {
+                    self.output_height = Some(height);
                 }
-                TokenUpdate::ActivationToken { token, .. } => {
-                    let mut cmd = std::process::Command::new("cosmic-settings");
-                    cmd.arg("bluetooth");
-                    if let Some(token) = token {
-                        cmd.env("XDG_ACTIVATION_TOKEN", &token);
-                        cmd.env("DESKTOP_STARTUP_ID", &token);
-                    }
-                    tokio::spawn(cosmic::process::spawn(cmd));
+            }
+            Message::ConfirmForget(addr) => {
+                self.device_menu = None;
+                // the confirmation is part of the device's details
+                if let Some(addr) = addr
+                    && self.expanded_device != Some(addr)
+                {
+                    _ = self.update(Message::ToggleDeviceDetails(addr));
                 }
-            },
-            Message::Frame(instant) => self.timeline.now(instant),
-            Message::ToggleBluetooth(chain, enabled) => {
-                self.timeline.set_chain(chain).start();
-                if let Some(tx) = self.worker_tx.as_mut() {
-                    _ = tx.send(WorkerRequest::SetEnabled(enabled));
+                self.confirm_forget = addr;
+            }
+            Message::DeviceMenu(addr) => self.device_menu = addr,
+            Message::MoveFocus(forward) => self.move_focus(forward),
+            Message::ActivateFocused => {
+                let action = self
+                    .focused_device
+                    .and_then(|addr| self.device_map.as_ref()?.get(&addr))
+                    .and_then(|dev| self.primary_action(dev));
+                if let Some(action) = action {
+                    return self.update(action);
                 }
             }
-            Message::ToggleVisibleDevices(enabled) => {
-                self.show_visible_devices = enabled;
+            Message::ForgetDevice(addr) => {
+                self.confirm_forget = None;
+                self.connect_failures.remove(&addr);
+                if self.config.last_connected(addr).is_some() {
+                    self.config
+                        .last_connected
+                        .retain(|(a, _)| a.parse() != Ok(addr));
+                    self.save_config();
+                }
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    _ = tx.send(WorkerRequest::RemoveDevice(addr));
+                }
             }
-            Message::CloseRequested(_id) => {
-                self.popup = None;
-                if let Some(worker_tx) = self.worker_tx.as_ref() {
-                    _ = worker_tx.send(WorkerRequest::SetDiscovery(false));
+            Message::Surface(a) => {
+                return cosmic::task::message(cosmic::Action::Cosmic(
+                    cosmic::app::Action::Surface(a),
+                ));
+            }
+            Message::SnoozeNotifications(duration) => {
+                self.snoozed_until = duration.map(|d| std::time::Instant::now() + d);
+            }
+            Message::ToggleErrorDetails(addr) => {
+                if self.expanded_error == Some(addr) {
+                    self.expanded_error = None;
+                } else {
+                    self.expanded_error = Some(addr);
                 }
             }
-            Message::ConfirmCode(addr, confirm) => {
-                if let Some(worker_tx) = self.worker_tx.as_ref() {
-                    _ = worker_tx.send(WorkerRequest::ConfirmCode(addr, confirm));
+            Message::SetHeadsetProfile(chain, addr, enabled) => {
+                self.timeline.set_chain(chain).start();
+
+                let addr_str = addr.to_string();
+                self.config
+                    .headset_profile_disabled
+                    .retain(|a| *a != addr_str);
+                if !enabled {
+                    self.config.headset_profile_disabled.push(addr_str);
+                }
+                self.save_config();
+
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    _ = tx.send(WorkerRequest::SetHeadsetProfile(addr, enabled));
+                }
+            }
+            Message::SetTrusted(chain, addr, trusted) => {
+                self.timeline.set_chain(chain).start();
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    _ = tx.send(WorkerRequest::SetTrusted(addr, trusted));
+                }
+            }
+            Message::SetWakeAllowed(chain, addr, allowed) => {
+                self.timeline.set_chain(chain).start();
+                if let Some(tx) = self.worker_tx.as_ref() {
+                    _ = tx.send(WorkerRequest::SetWakeAllowed(addr, allowed));
                 }
             }
+            Message::SetPrimaryDevice(addr) => self.set_primary_device(addr),
+            Message::ToggleAdapterInfo => {
+                self.show_adapter_info = !self.show_adapter_info;
+            }
+            Message::CopyAddress(addr) => {
+                return cosmic::iced::clipboard::write(addr.to_string());
+            }
         };
         Task::none()
     }
 
     fn subscription(&self) -> Subscription<Self::Message> {
+        let connecting = self
+            .device_map
+            .as_ref()
+            .is_some_and(|d| d.values().any(|dev| dev.connecting_since.is_some()));
+        // also counts down the time left discoverable, to answer pairing codes and until notifications are back,
+        // clears old warnings and keeps connection durations current
+        let connect_tick = if connecting
+            || self.power_pending_since.is_some()
+            || (self.popup.is_some() && self.connected_count() > 0)
+            || self.discoverable_until.is_some()
+            || !self.pending_confirmations.is_empty()
+            || !self.warnings.is_empty()
+            || (self.popup.is_some() && self.discovering_since.is_some())
+            || (self.popup.is_some() && self.snooze_remaining().is_some())
+        {
+            cosmic::iced::time::every(Duration::from_secs(1)).map(|_| Message::ConnectTick)
+        } else {
+            Subscription::none()
+        };
+
+        let focus = if self.config.resync_on_focus && self.popup.is_some() {
+            listen_with(|e, _, id| match e {
+                cosmic::iced_core::Event::Window(window::Event::Focused) => {
+                    Some(Message::WindowFocused(id))
+                }
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+
+        // only while a code is shown, so Escape keeps its usual meaning otherwise
+        let confirm_keys = if self.popup.is_some() && !self.pending_confirmations.is_empty() {
+            listen_with(|e, status, _| match e {
+                cosmic::iced_core::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Named(key),
+                    ..
+                }) if status == event::Status::Ignored => match key {
+                    keyboard::key::Named::Enter => Some(Message::AnswerConfirmation(true)),
+                    keyboard::key::Named::Escape => Some(Message::AnswerConfirmation(false)),
+                    _ => None,
+                },
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+
+        // pairing codes take Enter while they're shown
+        let navigation_keys = if self.popup.is_some() && self.pending_confirmations.is_empty() {
+            listen_with(|e, status, _| match e {
+                cosmic::iced_core::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key: keyboard::Key::Named(key),
+                    ..
+                }) if status == event::Status::Ignored => match key {
+                    keyboard::key::Named::ArrowDown => Some(Message::MoveFocus(true)),
+                    keyboard::key::Named::ArrowUp => Some(Message::MoveFocus(false)),
+                    keyboard::key::Named::Enter => Some(Message::ActivateFocused),
+                    _ => None,
+                },
+                _ => None,
+            })
+        } else {
+            Subscription::none()
+        };
+
+        // the popup may not grow past the output the panel is on
+        let outputs = listen_with(|e, _, _| match e {
+            cosmic::iced_core::Event::PlatformSpecific(event::PlatformSpecific::Wayland(
+                event::wayland::Event::Output(
+                    event::wayland::OutputEvent::Created(Some(info))
+                    | event::wayland::OutputEvent::InfoUpdate(info),
+                    _,
+                ),
+            )) => info
+                .logical_size
+                .map(|(_, height)| Message::OutputHeight(info.name, height as f32)),
+            _ => None,
+        });
+
         Subscription::batch([
+            connect_tick,
+            focus,
+            outputs,
+            confirm_keys,
+            navigation_keys,
             subscription::activation_token_subscription(0).map(Message::Token),
             Subscription::run(worker::spawn_worker).map(Message::BluetoothEvent),
+            Subscription::run(dbus::requests).map(Message::Request),
+            Subscription::run(notification::actions)
+                .map(|(id, key)| Message::NotificationAction(id, key)),
             self.timeline
                 .as_subscription()
                 .map(|(_, now)| Message::Frame(now)),
+            self.core.watch_config(Self::APP_ID).map(|u| {
+                for err in u.errors {
+                    tracing::error!(?err, "Error watching config");
+                }
+                Message::ConfigChanged(u.config)
+            }),
         ])
     }
 
     fn view(&self) -> Element<'_, Self::Message> {
-        let icon_name = if self.enabled {
+        // turning bluetooth on or off counts as a transition too, a middle click has no popup to show it
+        let icon_name = if self.worker_error.is_some() {
+            "dialog-error-symbolic"
+        } else if self.toggle_target != self.enabled || (self.enabled && self.in_transition()) {
+            "cosmic-applet-bluetooth-acquiring-symbolic"
+        } else if self.enabled && self.connected_count() > 0 {
+            "cosmic-applet-bluetooth-connected-symbolic"
+        } else if self.enabled {
             "cosmic-applet-bluetooth-active-symbolic"
         } else {
             "cosmic-applet-bluetooth-disabled-symbolic"
@@ -273,8 +2541,19 @@ impl cosmic::Application for CosmicBluetoothApplet {
 
         self.core
             .applet
-            .icon_button(icon_name)
-            .on_press_down(Message::TogglePopup)
+            .applet_tooltip::<Message>(
+                mouse_area(
+                    self.core
+                        .applet
+                        .icon_button(icon_name)
+                        .on_press_down(Message::TogglePopup),
+                )
+                .on_middle_press(Message::ToggleBluetoothQuick),
+                self.tooltip(),
+                self.popup.is_some(),
+                Message::Surface,
+                None,
+            )
             .into()
     }
 
@@ -287,114 +2566,313 @@ impl cosmic::Application for CosmicBluetoothApplet {
             space_xxs, space_s, ..
         } = cosmic::theme::active().cosmic().spacing;
 
-        let (paired, unpaired) = if let Some(device_map) = self.device_map.as_ref() {
-            let (mut paired, mut unpaired): (Vec<&BluetoothDevice>, Vec<&BluetoothDevice>) =
-                device_map.values().partition(|d| d.is_paired);
+        let (paired, unpaired) = self.device_lists();
 
-            paired.sort_by_key(|f| &f.name);
-            unpaired.sort_by_key(|f| &f.name);
-
-            (paired, unpaired)
-        } else {
-            (vec![], vec![])
-        };
+        let primary_device = self.config.primary_device();
+        let likely_category = DeviceCategory::most_used(paired.iter().copied());
 
         // build list of paired bluetooth devices
+        let paired_count = paired.len();
+        let paired_categories: Vec<_> = paired.iter().map(|dev| dev.category).collect();
         let paired: Vec<Element<'_, Message>> = paired
             .into_iter()
             .map(|dev| {
-                let mut row = row![
-                    icon::from_name(dev.icon).size(16).symbolic(true),
-                    text::body(dev.name.as_str())
-                        .align_x(Alignment::Start)
-                        .align_y(Alignment::Center)
-                        .width(Length::Fill)
-                ]
-                .align_y(Alignment::Center)
-                .spacing(12);
-
-                if let Some(battery) = dev.battery_percent {
-                    let icon = match battery {
-                        b if (20..40).contains(&b) => "battery-low",
-                        b if b < 20 => "battery-caution",
-                        _ => "battery",
-                    };
-                    let status = row!(
-                        icon::from_name(icon).symbolic(true).size(14),
-                        text::body(format!("{battery}%"))
-                    )
+                let mut row = row![self.device_icon(dev).size(16).symbolic(true)]
                     .align_y(Alignment::Center)
-                    .spacing(2)
-                    .width(Length::Shrink);
-
-                    let content = container(status)
-                        .align_x(Alignment::End)
-                        .align_y(Alignment::Center);
+                    .spacing(12);
 
-                    row = row.push(content);
+                let mut name = row![text::body(self.config.device_name(dev.address, &dev.name))]
+                    .align_y(Alignment::Center)
+                    .spacing(4);
+                if primary_device == Some(dev.address) {
+                    name = name.push(icon::from_name("starred-symbolic").size(12).symbolic(true));
                 }
 
-                match dev.status {
-                    ConnectionStatus::Connected => {
-                        row = row.push(
-                            text::body(fl!("connected"))
-                                .align_x(Alignment::End)
-                                .align_y(Alignment::Center),
-                        );
-                    }
-                    ConnectionStatus::Connecting | ConnectionStatus::Disconnecting => {
-                        // TODO make more consistent with spinning icon on cosmic-greeter?
-                        row = row.push(
-                            icon::from_name("process-working-symbolic")
-                                .size(24)
-                                .symbolic(true),
-                        );
-                    }
-                    ConnectionStatus::Disconnected => {}
+                let mut name_column = column![name].width(Length::Fill);
+                // compact rows are a single line, without the captions
+                if dev.is_blocked && !self.config.compact_list {
+                    name_column = name_column.push(text::caption(fl!("blocked")));
+                } else if dev.is_trusted && !self.config.compact_list {
+                    name_column = name_column.push(text::caption(fl!("auto-connect")));
+                }
+                if let Some(adapter) = dev.adapter.as_deref()
+                    && !self.config.compact_list
+                {
+                    name_column = name_column.push(text::caption(fl!(
+                        "on-adapter",
+                        HashMap::from([("adapter", adapter)])
+                    )));
                 }
+                row = row.push(name_column);
 
-                let mut button = menu_button(row);
-                match dev.status {
-                    ConnectionStatus::Connected => {
-                        button = button.on_press(Message::Request(WorkerRequest::DisconnectDevice(
-                            dev.address,
-                        )))
-                    }
-                    ConnectionStatus::Connecting => {
-                        button = button
-                            .on_press(Message::Request(WorkerRequest::CancelConnect(dev.address)))
-                    }
-                    ConnectionStatus::Disconnected => {
-                        button = button
-                            .on_press(Message::Request(WorkerRequest::ConnectDevice(dev.address)))
-                    }
-                    _ => {}
+                if self.config.compact_list {
+                    row = row.extend(status_dot(dev.status));
+                } else {
+                    row = row.extend(self.device_battery(dev));
+                    row = row.extend(self.device_status(dev));
                 }
 
-                button.into()
+                let expanded = self.expanded_device == Some(dev.address);
+                row = row.push(
+                    button::icon(
+                        icon::from_name(if expanded {
+                            "go-up-symbolic"
+                        } else {
+                            "go-down-symbolic"
+                        })
+                        .size(16),
+                    )
+                    .icon_size(16)
+                    .on_press(Message::ToggleDeviceDetails(dev.address)),
+                );
+
+                let button = menu_button(dim_blocked(row, dev.is_blocked))
+                    .selected(self.focused_device == Some(dev.address))
+                    .on_press_maybe(self.primary_action(dev));
+
+                let mut entry = column![self.device_row(button, dev)];
+                if let Some(err) = dev.connect_error.as_ref() {
+                    entry = entry.push(self.connect_error(dev.address, err));
+                }
+                if expanded {
+                    entry = entry.push(self.device_details(dev));
+                }
+                entry.into()
             })
             .collect();
 
-        let mut content = column![padded_control(anim!(
-            BLUETOOTH_ENABLED,
-            &self.timeline,
-            fl!("bluetooth"),
-            self.enabled,
-            Message::ToggleBluetooth,
-        ))]
+        let paired = self.group_by_category(paired_categories, paired);
+
+        // both lists draw from one height budget, so together they still fit on screen
+        let paired_scrolls = paired_count > SCROLL_AFTER_DEVICES;
+        let unpaired_scrolls =
+            self.config.show_visible_devices && unpaired.len() > SCROLL_AFTER_DEVICES;
+        let list_height = if paired_scrolls && unpaired_scrolls {
+            self.config.list_max_height() / 2.0
+        } else {
+            self.config.list_max_height()
+        };
+
+        let mut content = if self.hard_blocked {
+            // the toggle can't change anything until the block is lifted, so it stays disabled
+            column![
+                padded_control(
+                    toggler(self.enabled)
+                        .label(fl!("bluetooth"))
+                        .width(Length::Fill)
+                ),
+                padded_control(
+                    row![
+                        icon::from_name("airplane-mode-symbolic")
+                            .size(16)
+                            .symbolic(true),
+                        text::caption(fl!("hard-blocked")).width(Length::Fill),
+                    ]
+                    .align_y(Alignment::Center)
+                    .spacing(8)
+                ),
+            ]
+        } else {
+            let label = match self.power_pending_since {
+                Some(_) if self.toggle_target => fl!("turning-on"),
+                Some(_) => fl!("turning-off"),
+                None => fl!("bluetooth"),
+            };
+            column![padded_control(anim!(
+                BLUETOOTH_ENABLED,
+                &self.timeline,
+                label,
+                self.enabled,
+                Message::ToggleBluetooth,
+            ))]
+        }
         .align_x(Alignment::Center)
         .padding([8, 0]);
 
+        if !self.warnings.is_empty() {
+            content = content.push(self.warnings_banner());
+        }
+
+        if self.enabled && self.device_map.is_some() {
+            let chips = [
+                (FilterFlags::PAIRED, fl!("filter-paired")),
+                (FilterFlags::CONNECTED, fl!("filter-connected")),
+                (FilterFlags::AUDIO, fl!("filter-audio")),
+            ]
+            .into_iter()
+            .map(|(flag, label)| {
+                button::text(label)
+                    .selected(self.filter_flags.contains(flag))
+                    .on_press(Message::ToggleFilterFlag(flag))
+                    .into()
+            });
+            content = content.push(padded_control(Row::with_children(chips).spacing(8)));
+        }
+
+        if self.enabled {
+            content = content.push(padded_control(
+                anim!(
+                    DISCOVERABLE,
+                    &self.timeline,
+                    fl!("make-visible"),
+                    self.discoverable,
+                    Message::SetDiscoverable,
+                )
+                .text_size(14)
+                .width(Length::Fill),
+            ));
+
+            if let Some(until) = self.discoverable_until {
+                let remaining = until.saturating_duration_since(std::time::Instant::now());
+                content = content.push(padded_control(
+                    text::caption(fl!(
+                        "visible-remaining",
+                        HashMap::from([(
+                            "time",
+                            format!(
+                                "{}:{:02}",
+                                remaining.as_secs() / 60,
+                                remaining.as_secs() % 60
+                            )
+                        )])
+                    ))
+                    .width(Length::Fill),
+                ));
+            }
+
+            content = content.push(padded_control(
+                anim!(
+                    PAIRABLE,
+                    &self.timeline,
+                    fl!("accept-pairing"),
+                    self.pairable,
+                    Message::SetPairable,
+                )
+                .text_size(14)
+                .width(Length::Fill),
+            ));
+        }
+
+        if self.reconnecting {
+            content = content.push(padded_control(
+                text::body(fl!("reconnecting")).width(Length::Fill),
+            ));
+        }
+
+        if self.no_adapter {
+            content = content.push(padded_control(
+                text::body(fl!("no-adapter")).width(Length::Fill),
+            ));
+        }
+
+        if let Some(err) = self.worker_error.as_deref() {
+            content = content.push(padded_control(
+                column![
+                    row![
+                        icon::from_name("dialog-error-symbolic")
+                            .size(16)
+                            .symbolic(true),
+                        text::body(fl!("bluetooth-unavailable")).width(Length::Fill),
+                    ]
+                    .align_y(Alignment::Center)
+                    .spacing(8),
+                    text::caption(err.lines().next().unwrap_or_default().to_string()),
+                ]
+                .spacing(4),
+            ));
+        }
+
+        // stacked, so several devices pairing at once each keep their own prompt
+        for confirmation in self.confirmations() {
+            content = content.extend([
+                confirmation,
+                padded_control(divider::horizontal::default())
+                    .padding([space_xxs, space_s])
+                    .into(),
+            ]);
+        }
+
+        for (addr, service) in &self.authorizations {
+            let addr = *addr;
+            let mut buttons = row![
+                button::custom(text::body(fl!("deny")).center())
+                    .padding([4, 0])
+                    .height(Length::Fixed(28.0))
+                    .width(Length::Fixed(105.0))
+                    .on_press(Message::AuthorizeService(addr, false, false)),
+                button::custom(text::body(fl!("allow")).center())
+                    .padding([4, 0])
+                    .height(Length::Fixed(28.0))
+                    .width(Length::Fixed(105.0))
+                    .on_press(Message::AuthorizeService(addr, true, false)),
+            ]
+            .spacing(space_xxs)
+            .width(Length::Shrink)
+            .align_y(Alignment::Center);
+            // pairing is a one time question, only services come back on every reconnect
+            if service.is_some() {
+                buttons = buttons.push(
+                    button::custom(text::body(fl!("always-allow")).center())
+                        .padding([4, 0])
+                        .height(Length::Fixed(28.0))
+                        .width(Length::Fixed(105.0))
+                        .on_press(Message::AuthorizeService(addr, true, true)),
+                );
+            }
+
+            content = content.extend([
+                padded_control(
+                    text::body(self.authorization_text(addr, *service)).width(Length::Fill),
+                )
+                .into(),
+                padded_control(buttons).align_x(Alignment::Center).into(),
+            ]);
+        }
+
         if !paired.is_empty() {
+            let recent = self.config.paired_sort == PairedSort::Recent;
             content = content.extend([
                 padded_control(divider::horizontal::default())
                     .padding([space_xxs, space_s])
                     .into(),
-                Column::with_children(paired).into(),
-            ])
+                padded_control(
+                    row![
+                        text::caption(fl!("paired-devices")).width(Length::Fill),
+                        button::icon(icon::from_name("view-compact-symbolic").size(16))
+                            .icon_size(16)
+                            .selected(self.config.compact_list)
+                            .tooltip(fl!("compact-list"))
+                            .on_press(Message::SetCompactList(!self.config.compact_list)),
+                        button::icon(icon::from_name("document-open-recent-symbolic").size(16))
+                            .icon_size(16)
+                            .selected(recent)
+                            .tooltip(fl!("sort-by-recent"))
+                            .on_press(Message::SetPairedSort(if recent {
+                                PairedSort::Name
+                            } else {
+                                PairedSort::Recent
+                            })),
+                    ]
+                    .align_y(Alignment::Center),
+                )
+                .into(),
+            ]);
+
+            content = if paired_scrolls {
+                content.push(
+                    scrollable(Column::with_children(paired))
+                        .id(PAIRED_LIST.clone())
+                        .on_scroll(|viewport| {
+                            Message::Scrolled(PAIRED_LIST.clone(), viewport.absolute_offset())
+                        })
+                        .height(Length::Fixed(list_height)),
+                )
+            } else {
+                content.extend(paired)
+            };
         }
 
-        let dropdown_icon = if self.show_visible_devices {
+        let dropdown_icon = if self.config.show_visible_devices {
             "go-up-symbolic"
         } else {
             "go-down-symbolic"
@@ -403,15 +2881,50 @@ impl cosmic::Application for CosmicBluetoothApplet {
         let mut list_column: Vec<Element<'_, Message>> = Vec::new();
 
         if self.enabled {
-            let available_connections_btn = menu_button(row![
+            let (sort_icon, next_sort) = match self.config.nearby_sort {
+                DeviceSort::Name => ("view-sort-ascending-symbolic", DeviceSort::Signal),
+                DeviceSort::Signal => ("network-wireless-signal-good-symbolic", DeviceSort::Name),
+            };
+            let mut title = row![
                 text::body(fl!("other-devices"))
-                    .width(Length::Fill)
                     .height(Length::Fixed(24.0))
-                    .align_y(Alignment::Center),
+                    .align_y(Alignment::Center)
+            ]
+            .align_y(Alignment::Center)
+            .spacing(8)
+            .width(Length::Fill);
+            if let Some(since) = self.discovering_since {
+                // the dots count up every second, so the scan visibly goes on
+                let dots = ".".repeat(since.elapsed().as_secs() as usize % 3 + 1);
+                title = title.push(text::caption(format!("{}{dots}", fl!("searching"))));
+            }
+            let available_connections_btn = menu_button(row![
+                title,
+                button::icon(icon::from_name("view-list-symbolic").size(16))
+                    .icon_size(16)
+                    .selected(self.config.group_by_type)
+                    .tooltip(fl!("group-by-type"))
+                    .on_press(Message::SetGroupByType(!self.config.group_by_type)),
+                button::icon(icon::from_name(sort_icon).size(16))
+                    .icon_size(16)
+                    .on_press(Message::SetNearbySort(next_sort)),
+                button::icon(
+                    icon::from_name(if self.refreshing {
+                        "process-working-symbolic"
+                    } else {
+                        "view-refresh-symbolic"
+                    })
+                    .size(16),
+                )
+                .icon_size(16)
+                .tooltip(fl!("refresh-devices"))
+                .on_press_maybe((!self.refreshing).then_some(Message::RefreshDevices)),
                 container(icon::from_name(dropdown_icon).size(16).symbolic(true))
                     .center(Length::Fixed(24.0))
             ])
-            .on_press(Message::ToggleVisibleDevices(!self.show_visible_devices));
+            .on_press(Message::ToggleVisibleDevices(
+                !self.config.show_visible_devices,
+            ));
 
             content = content.extend([
                 padded_control(divider::horizontal::default())
@@ -420,72 +2933,363 @@ impl cosmic::Application for CosmicBluetoothApplet {
                 available_connections_btn.into(),
             ]);
 
-            list_column.extend(unpaired.into_iter().map(|dev| {
-                if let Some(code) = dev.display_code.as_ref() {
-                    column![
-                        padded_control(
-                            row![
-                                icon::from_name(dev.icon).size(16).symbolic(true),
-                                text::body(dev.name.clone()).align_x(Alignment::Start),
-                            ]
-                            .align_y(Alignment::Center)
-                            .spacing(12)
-                        ),
-                        padded_control(
-                            text::body(fl!(
-                                "confirm-pin",
-                                HashMap::from([("deviceName", dev.name.clone())])
-                            ))
-                            .align_x(Alignment::Start)
-                            .align_y(Alignment::Center)
-                            .width(Length::Fill)
-                        ),
-                        padded_control(text::title3(code).center().width(Length::Fixed(280.0)))
-                            .align_x(Alignment::Center),
-                        padded_control(
-                            row![
-                                button::custom(text::body(fl!("cancel")).center())
-                                    .padding([4, 0])
-                                    .height(Length::Fixed(28.0))
-                                    .width(Length::Fixed(105.0))
-                                    .on_press(Message::ConfirmCode(dev.address, false)),
-                                button::custom(text::body(fl!("confirm")).center())
-                                    .padding([4, 0])
-                                    .height(Length::Fixed(28.0))
-                                    .width(Length::Fixed(105.0))
-                                    .on_press(Message::ConfirmCode(dev.address, true)),
-                            ]
-                            .spacing(self.core.system_theme().cosmic().space_xxs())
-                            .width(Length::Shrink)
-                            .align_y(Alignment::Center)
-                        )
-                        .align_x(Alignment::Center)
-                    ]
-                    .into()
-                } else {
-                    let row = row![
-                        icon::from_name(dev.icon).size(16).symbolic(true),
-                        text::body(dev.name.clone())
-                            .align_x(Alignment::Start)
-                    ]
-                    .align_y(Alignment::Center)
-                    .spacing(12);
+            if self.config.show_visible_devices {
+                content = content.push(padded_control(
+                    text_input::search_input(fl!("filter-devices"), self.filter.as_str())
+                        .on_input(Message::FilterChanged)
+                        .on_clear(Message::ClearFilter),
+                ));
 
-                    menu_button(row.width(Length::Fill))
-                        .on_press(Message::Request(WorkerRequest::ConnectDevice(dev.address)))
-                        .into()
+                if self.scan_timed_out {
+                    content = content.push(padded_control(
+                        row![
+                            text::caption(fl!("scan-stopped")).width(Length::Fill),
+                            button::text(fl!("scan-again"))
+                                .on_press(Message::Request(WorkerRequest::SetDiscovery(true))),
+                        ]
+                        .align_y(Alignment::Center)
+                        .spacing(8),
+                    ));
                 }
-            }))
+            }
+
+            // point the user at pairing mode when nothing they're likely looking for is nearby
+            if self.filter.trim().is_empty()
+                && self.filter_flags.is_empty()
+                && !unpaired
+                    .iter()
+                    .any(|dev| likely_category.is_none_or(|category| dev.category == category))
+            {
+                let category = match likely_category {
+                    Some(DeviceCategory::Audio) => "audio",
+                    Some(DeviceCategory::Input) => "input",
+                    _ => "other",
+                };
+                list_column.push(
+                    padded_control(
+                        text::caption(fl!(
+                            "no-devices-nearby",
+                            HashMap::from([("category", category)])
+                        ))
+                        .width(Length::Fill),
+                    )
+                    .into(),
+                );
+            }
+
+            let unpaired_categories: Vec<_> = unpaired.iter().map(|dev| dev.category).collect();
+            let unpaired: Vec<Element<'_, Message>> = unpaired
+                .into_iter()
+                .map(|dev| {
+                    if let Some(pin) = self.pin_entry.get(&dev.address) {
+                        let addr = dev.address;
+                        column![
+                            padded_control(
+                                row![
+                                    self.device_icon(dev).size(16).symbolic(true),
+                                    text::body(
+                                        self.config.device_name(dev.address, &dev.name).to_string()
+                                    )
+                                    .align_x(Alignment::Start),
+                                ]
+                                .align_y(Alignment::Center)
+                                .spacing(12)
+                            ),
+                            padded_control(
+                                text::body(fl!(
+                                    "enter-pin",
+                                    HashMap::from([(
+                                        "deviceName",
+                                        self.config.device_name(dev.address, &dev.name).to_string()
+                                    )])
+                                ))
+                                .align_x(Alignment::Start)
+                                .align_y(Alignment::Center)
+                                .width(Length::Fill)
+                            ),
+                            padded_control(
+                                text_input::text_input("", pin.as_str())
+                                    .on_input(move |pin| Message::PinInput(addr, pin))
+                                    .on_submit(move |pin| Message::SubmitPinCode(addr, pin))
+                            ),
+                            padded_control(
+                                row![
+                                    button::custom(text::body(fl!("cancel")).center())
+                                        .padding([4, 0])
+                                        .height(Length::Fixed(28.0))
+                                        .width(Length::Fixed(105.0))
+                                        .on_press(Message::ConfirmCode(addr, false)),
+                                    button::custom(text::body(fl!("confirm")).center())
+                                        .padding([4, 0])
+                                        .height(Length::Fixed(28.0))
+                                        .width(Length::Fixed(105.0))
+                                        .on_press(Message::SubmitPinCode(addr, pin.clone())),
+                                ]
+                                .spacing(self.core.system_theme().cosmic().space_xxs())
+                                .width(Length::Shrink)
+                                .align_y(Alignment::Center)
+                            )
+                            .align_x(Alignment::Center)
+                        ]
+                        .into()
+                    } else if let Some((code, entered)) = self.displayed_passkeys.get(&dev.address)
+                    {
+                        let mut panel = column![
+                            padded_control(
+                                row![
+                                    self.device_icon(dev).size(16).symbolic(true),
+                                    text::body(
+                                        self.config.device_name(dev.address, &dev.name).to_string()
+                                    )
+                                    .align_x(Alignment::Start),
+                                ]
+                                .align_y(Alignment::Center)
+                                .spacing(12)
+                            ),
+                            padded_control(
+                                text::body(fl!(
+                                    "type-passkey",
+                                    HashMap::from([(
+                                        "deviceName",
+                                        self.config.device_name(dev.address, &dev.name).to_string()
+                                    )])
+                                ))
+                                .align_x(Alignment::Start)
+                                .align_y(Alignment::Center)
+                                .width(Length::Fill)
+                            ),
+                            padded_control(text::title3(code).center().width(Length::Fixed(280.0)))
+                                .align_x(Alignment::Center),
+                        ];
+                        if let Some(entered) = entered {
+                            panel = panel.push(
+                                padded_control(
+                                    text::caption(fl!(
+                                        "passkey-entered",
+                                        HashMap::from([
+                                            ("entered", entered.to_string()),
+                                            ("total", code.len().to_string())
+                                        ])
+                                    ))
+                                    .center()
+                                    .width(Length::Fill),
+                                )
+                                .align_x(Alignment::Center),
+                            );
+                        }
+                        panel
+                            .push(
+                                padded_control(
+                                    button::custom(text::body(fl!("cancel")).center())
+                                        .padding([4, 0])
+                                        .height(Length::Fixed(28.0))
+                                        .width(Length::Fixed(105.0))
+                                        .on_press(Message::Request(WorkerRequest::CancelConnect(
+                                            dev.address,
+                                        ))),
+                                )
+                                .align_x(Alignment::Center),
+                            )
+                            .into()
+                    } else {
+                        let mut row = row![
+                            self.device_icon(dev).size(16).symbolic(true),
+                            text::body(self.config.device_name(dev.address, &dev.name).to_string())
+                                .align_x(Alignment::Start)
+                                .width(Length::Fill)
+                        ]
+                        .align_y(Alignment::Center)
+                        .spacing(12);
+                        // cached devices that weren't seen in this scan have no rssi, rather than a weak one
+                        if let Some(rssi) = dev.rssi {
+                            row = row
+                                .push(icon::from_name(signal_icon(rssi)).size(16).symbolic(true));
+                        }
+
+                        let select =
+                            menu_button(dim_blocked(row.width(Length::Fill), dev.is_blocked))
+                                .selected(self.focused_device == Some(dev.address))
+                                .on_press_maybe(self.primary_action(dev));
+                        let select = self.device_row(select, dev);
+                        if !self.config.confirm_before_pair
+                            || self.pair_candidate != Some(dev.address)
+                        {
+                            return select;
+                        }
+
+                        column![
+                            select,
+                            padded_control(
+                                row![
+                                    text::caption(dev.address.to_string()).width(Length::Fill),
+                                    button::standard(fl!("cancel"))
+                                        .on_press(Message::SelectPairCandidate(None)),
+                                    button::suggested(fl!("pair")).on_press(Message::Request(
+                                        WorkerRequest::ConnectDevice(dev.address)
+                                    )),
+                                ]
+                                .align_y(Alignment::Center)
+                                .spacing(8)
+                            ),
+                        ]
+                        .into()
+                    }
+                })
+                .collect();
+            list_column.extend(self.group_by_category(unpaired_categories, unpaired));
+        }
+
+        if !self.config.show_visible_devices {
+            list_column.clear();
         }
 
-        if list_column.len() > 10 {
-            content = content
-                .push(scrollable(Column::with_children(list_column)).height(Length::Fixed(300.0)));
+        if unpaired_scrolls {
+            content = content.push(
+                scrollable(Column::with_children(list_column))
+                    .id(NEARBY_LIST.clone())
+                    .on_scroll(|viewport| {
+                        Message::Scrolled(NEARBY_LIST.clone(), viewport.absolute_offset())
+                    })
+                    .height(Length::Fixed(list_height)),
+            );
         } else {
             content = content.extend(list_column);
         }
 
+        if let Some(info) = self.adapter_info.as_ref() {
+            let dropdown_icon = if self.show_adapter_info {
+                "go-up-symbolic"
+            } else {
+                "go-down-symbolic"
+            };
+
+            content = content.extend([
+                padded_control(divider::horizontal::default())
+                    .padding([space_xxs, space_s])
+                    .into(),
+                menu_button(row![
+                    text::body(fl!("adapter-info"))
+                        .width(Length::Fill)
+                        .height(Length::Fixed(24.0))
+                        .align_y(Alignment::Center),
+                    container(icon::from_name(dropdown_icon).size(16).symbolic(true))
+                        .center(Length::Fixed(24.0))
+                ])
+                .on_press(Message::ToggleAdapterInfo)
+                .into(),
+            ]);
+
+            // picking an adapter only makes sense with more than one
+            if self.show_adapter_info && self.adapters.len() > 1 {
+                content = content.extend(self.adapters.iter().map(|name| {
+                    let selected = *name == info.name;
+                    let mut row = row![text::body(name.as_str()).width(Length::Fill)]
+                        .align_y(Alignment::Center);
+                    if selected {
+                        row = row.push(icon::from_name("object-select-symbolic").size(16));
+                    }
+
+                    let mut button = menu_button(row);
+                    if !selected {
+                        button = button
+                            .on_press(Message::Request(WorkerRequest::SelectAdapter(name.clone())));
+                    }
+                    button.into()
+                }));
+            }
+
+            if self.show_adapter_info {
+                content = content.push(padded_control(
+                    row![
+                        column![
+                            text::body(info.name.as_str()),
+                            text::caption(info.address.to_string()),
+                        ]
+                        .width(Length::Fill),
+                        button::icon(icon::from_name("edit-copy-symbolic").size(16))
+                            .icon_size(16)
+                            .on_press(Message::CopyAddress(info.address)),
+                    ]
+                    .align_y(Alignment::Center)
+                    .spacing(8),
+                ));
+
+                let alias = self.adapter_alias.as_deref().unwrap_or(&info.alias);
+                content = content.push(padded_control(
+                    column![
+                        text::caption(fl!("adapter-alias")),
+                        text_input::text_input(fl!("adapter-alias-placeholder"), alias)
+                            .on_input(Message::AdapterAliasInput)
+                            .on_submit(Message::SubmitAdapterAlias),
+                    ]
+                    .spacing(4),
+                ));
+            }
+        }
+
+        if self.connected_count() > 0 {
+            content = content.push(
+                menu_button(text::body(fl!("disconnect-all")))
+                    .on_press(Message::Request(WorkerRequest::DisconnectAll)),
+            );
+        }
+
+        // a hardware switch overrides airplane mode, so there's nothing to leave or enter
+        if !self.hard_blocked {
+            let airplane = self.config.airplane_mode && self.soft_blocked;
+            let label = if airplane {
+                fl!("leave-airplane-mode")
+            } else {
+                fl!("enter-airplane-mode")
+            };
+            content = content.push(
+                menu_button(
+                    row![
+                        icon::from_name("airplane-mode-symbolic")
+                            .size(16)
+                            .symbolic(true),
+                        text::body(label),
+                    ]
+                    .align_y(Alignment::Center)
+                    .spacing(8),
+                )
+                .on_press(Message::SetAirplane(!airplane)),
+            );
+        }
+
+        let snooze = if let Some(remaining) = self.snooze_remaining() {
+            // round up so the last minute doesn't read as 0
+            let minutes = remaining.as_secs().div_ceil(60).to_string();
+            row![
+                text::body(fl!(
+                    "notifications-snoozed",
+                    HashMap::from([("minutes", minutes)])
+                ))
+                .width(Length::Fill),
+                button::text(fl!("resume")).on_press(Message::SnoozeNotifications(None)),
+            ]
+        } else {
+            SNOOZE_HOURS.iter().fold(
+                row![text::body(fl!("snooze-notifications")).width(Length::Fill)],
+                |row, hours| {
+                    row.push(
+                        button::text(fl!(
+                            "snooze-hours",
+                            HashMap::from([("hours", hours.to_string())])
+                        ))
+                        .on_press(Message::SnoozeNotifications(Some(
+                            Duration::from_secs(hours * 60 * 60),
+                        ))),
+                    )
+                },
+            )
+        };
+
         content = content.extend([
+            padded_control(divider::horizontal::default())
+                .padding([space_xxs, space_s])
+                .into(),
+            padded_control(snooze.align_y(Alignment::Center).spacing(4)).into(),
             padded_control(divider::horizontal::default())
                 .padding([space_xxs, space_s])
                 .into(),
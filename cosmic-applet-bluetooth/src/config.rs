@@ -1,4 +1,403 @@
 // Copyright 2023 System76 <info@system76.com>
 // SPDX-License-Identifier: GPL-3.0-only
 
+use std::time::Duration;
+
+use cosmic::cosmic_config::{self, CosmicConfigEntry, cosmic_config_derive::CosmicConfigEntry};
+use serde::{Deserialize, Serialize};
+
 pub const APP_ID: &str = "com.system76.CosmicAppletBluetooth";
+pub const DEFAULT_SETTINGS_COMMAND: &str = "cosmic-settings bluetooth";
+pub const DEFAULT_CONCURRENT_CONNECTS: usize = 2;
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_LIST_MAX_HEIGHT: u32 = 300;
+pub const DEFAULT_SCAN_DURATION_SECS: u64 = 10;
+pub const DEFAULT_SCAN_INTERVAL_SECS: u64 = 120;
+pub const DEFAULT_BATTERY_POLL_SECS: u64 = 300;
+pub const DEFAULT_DISCOVERABLE_TIMEOUT_SECS: u32 = 180;
+pub const DEFAULT_DISCOVERY_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_CONNECT_MAX_TRIES: u32 = 5;
+pub const DEFAULT_CONNECT_INITIAL_BACKOFF_MS: u64 = 500;
+pub const DEFAULT_CONNECT_MAX_BACKOFF_MS: u64 = 10_000;
+pub const DEFAULT_BATTERY_CAUTION_THRESHOLD: u8 = 20;
+pub const DEFAULT_BATTERY_LOW_THRESHOLD: u8 = 40;
+
+/// How device battery levels are drawn in the popup
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BatteryDisplay {
+    /// low/caution/full icons
+    #[default]
+    Icon,
+    /// ring filled to the exact percentage
+    Ring,
+}
+
+/// What turning bluetooth off in the applet does
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DisableMode {
+    /// power the adapter down through bluez, falling back to rfkill
+    #[default]
+    PowerOff,
+    /// soft-block the radio with rfkill, which is quicker to turn back on
+    SoftBlock,
+}
+
+/// Order of the nearby (unpaired) devices list
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DeviceSort {
+    #[default]
+    Name,
+    /// strongest signal first, devices without a name or signal last
+    Signal,
+}
+
+/// Order of the paired devices list
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PairedSort {
+    #[default]
+    Name,
+    /// most recently connected first, devices that never connected last
+    Recent,
+}
+
+/// How worried to be about a battery, per the configured thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatteryLevel {
+    /// below the caution threshold, the user is notified
+    Caution,
+    Low,
+    Normal,
+}
+
+/// How much the panel icon's tooltip says
+#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TooltipVerbosity {
+    /// just whether bluetooth is on
+    Status,
+    /// connected devices and their battery levels too
+    #[default]
+    Detailed,
+}
+
+/// How often a failed connect is retried, and how long to wait in between
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_tries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, CosmicConfigEntry, PartialEq, Eq)]
+#[version = 1]
+pub struct BluetoothAppletConfig {
+    pub battery_display: BatteryDisplay,
+    /// address of the device the user marked as their main one
+    pub primary_device: Option<String>,
+    /// command line run by the "Bluetooth settings..." button
+    pub settings_command: String,
+    pub disable_mode: DisableMode,
+    /// how many devices may be connecting at the same time, further connects wait their turn
+    pub concurrent_connects: usize,
+    /// seconds a connect may take, including retries, before it is given up
+    pub connect_timeout_secs: u64,
+    /// fixed popup width in logical pixels, `None` sizes it to the content
+    pub popup_width: Option<u32>,
+    /// fixed popup height in logical pixels, `None` sizes it to the content
+    pub popup_height: Option<u32>,
+    /// height the device lists can grow to together before they scroll
+    pub list_max_height: u32,
+    /// scan for devices periodically while the popup is closed
+    pub background_scan: bool,
+    /// how long each background scan runs
+    pub scan_duration_secs: u64,
+    /// pause between background scans
+    pub scan_interval_secs: u64,
+    /// also list devices paired with adapters other than the one in use
+    pub show_all_adapters: bool,
+    pub tooltip: TooltipVerbosity,
+    /// addresses of audio devices that should only connect for audio playback, without the headset (microphone) profile
+    pub headset_profile_disabled: Vec<String>,
+    /// refresh the device list whenever the popup regains focus
+    pub resync_on_focus: bool,
+    /// clicking a nearby device only selects it, pairing needs a second click on "Pair"
+    pub confirm_before_pair: bool,
+    /// reconnect trusted devices that were connected when bluetooth was turned off from the applet
+    pub auto_reconnect_trusted: bool,
+    pub nearby_sort: DeviceSort,
+    /// addresses of devices allowed to use any service without asking
+    pub always_allow_services: Vec<String>,
+    /// notify when a device connects or disconnects
+    pub connection_notifications: bool,
+    /// attempts per connect, including the first
+    pub connect_max_tries: u32,
+    /// wait after the first failed attempt, doubled after each further one
+    pub connect_initial_backoff_ms: u64,
+    /// longest wait between attempts
+    pub connect_max_backoff_ms: u64,
+    /// seconds between battery reads of connected devices, for devices that don't report changes. 0 turns it off
+    pub battery_poll_secs: u64,
+    /// how long "Make visible" keeps the computer discoverable
+    pub discoverable_timeout_secs: u32,
+    /// the "Other devices" section of the popup is expanded
+    pub show_visible_devices: bool,
+    /// seconds the popup scans for devices before stopping to save power. 0 scans until the popup closes
+    pub discovery_timeout_secs: u64,
+    /// group both device lists by device type, with a header per group
+    pub group_by_type: bool,
+    /// (device address or part of its name, icon name) pairs, for devices bluez doesn't know the type of. The
+    /// first match wins
+    pub icon_overrides: Vec<(String, String)>,
+    pub paired_sort: PairedSort,
+    /// leave nearby devices that have no name, only an address, out of the list. Paired devices are always shown
+    pub hide_unnamed_devices: bool,
+    /// batteries below this percentage are shown in red, and the user is notified once when one drops below it
+    pub battery_caution_threshold: u8,
+    /// batteries below this percentage are shown in yellow
+    pub battery_low_threshold: u8,
+    /// (device address, seconds since the unix epoch) of the last time each device connected
+    pub last_connected: Vec<(String, u64)>,
+    /// name of the adapter the user picked, used whenever it's plugged in
+    pub selected_adapter: Option<String>,
+    /// the applet soft-blocked the radio for airplane mode, cleared once anything lifts the block
+    pub airplane_mode: bool,
+    /// (device address, nickname) pairs, names shown only by this applet in place of the device's alias
+    pub nicknames: Vec<(String, String)>,
+    /// single-line paired device rows, with a dot in place of the battery and status details
+    pub compact_list: bool,
+    /// accept the pairing code of trusted devices without asking, when they pair again
+    pub auto_confirm_trusted: bool,
+}
+
+impl Default for BluetoothAppletConfig {
+    fn default() -> Self {
+        Self {
+            battery_display: BatteryDisplay::default(),
+            primary_device: None,
+            settings_command: DEFAULT_SETTINGS_COMMAND.to_string(),
+            disable_mode: DisableMode::default(),
+            concurrent_connects: DEFAULT_CONCURRENT_CONNECTS,
+            connect_timeout_secs: DEFAULT_CONNECT_TIMEOUT_SECS,
+            popup_width: None,
+            popup_height: None,
+            list_max_height: DEFAULT_LIST_MAX_HEIGHT,
+            background_scan: false,
+            scan_duration_secs: DEFAULT_SCAN_DURATION_SECS,
+            scan_interval_secs: DEFAULT_SCAN_INTERVAL_SECS,
+            show_all_adapters: false,
+            tooltip: TooltipVerbosity::default(),
+            headset_profile_disabled: Vec::new(),
+            resync_on_focus: false,
+            confirm_before_pair: false,
+            auto_reconnect_trusted: true,
+            nearby_sort: DeviceSort::default(),
+            always_allow_services: Vec::new(),
+            connection_notifications: true,
+            connect_max_tries: DEFAULT_CONNECT_MAX_TRIES,
+            connect_initial_backoff_ms: DEFAULT_CONNECT_INITIAL_BACKOFF_MS,
+            connect_max_backoff_ms: DEFAULT_CONNECT_MAX_BACKOFF_MS,
+            battery_poll_secs: DEFAULT_BATTERY_POLL_SECS,
+            discoverable_timeout_secs: DEFAULT_DISCOVERABLE_TIMEOUT_SECS,
+            show_visible_devices: true,
+            discovery_timeout_secs: DEFAULT_DISCOVERY_TIMEOUT_SECS,
+            group_by_type: false,
+            icon_overrides: Vec::new(),
+            paired_sort: PairedSort::default(),
+            hide_unnamed_devices: true,
+            battery_caution_threshold: DEFAULT_BATTERY_CAUTION_THRESHOLD,
+            battery_low_threshold: DEFAULT_BATTERY_LOW_THRESHOLD,
+            last_connected: Vec::new(),
+            selected_adapter: None,
+            airplane_mode: false,
+            nicknames: Vec::new(),
+            compact_list: false,
+            auto_confirm_trusted: true,
+        }
+    }
+}
+
+impl BluetoothAppletConfig {
+    /// Reads the current config, for code that can't watch it through the app
+    pub fn load() -> Self {
+        cosmic_config::Config::new(APP_ID, Self::VERSION)
+            .ok()
+            .map(|helper| Self::get_entry(&helper).unwrap_or_else(|(_, config)| config))
+            .unwrap_or_default()
+    }
+
+    /// The primary device, anything acting on "the" device should go through this
+    pub fn primary_device(&self) -> Option<bluer::Address> {
+        self.primary_device
+            .as_deref()
+            .and_then(|addr| addr.parse().ok())
+    }
+
+    /// Whether the headset profile should be connected alongside audio playback for a device
+    pub fn headset_profile_enabled(&self, addr: bluer::Address) -> bool {
+        !self
+            .headset_profile_disabled
+            .iter()
+            .any(|a| a.parse() == Ok(addr))
+    }
+
+    pub fn always_allow_services(&self, addr: bluer::Address) -> bool {
+        self.always_allow_services
+            .iter()
+            .any(|a| a.parse() == Ok(addr))
+    }
+
+    /// The icon the user picked for a device, matched by address or else by name
+    pub fn icon_override(&self, addr: bluer::Address, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        let by_address = self
+            .icon_overrides
+            .iter()
+            .find(|(pattern, _)| pattern.parse() == Ok(addr));
+        let by_name = || {
+            self.icon_overrides
+                .iter()
+                .find(|(pattern, _)| !pattern.is_empty() && name.contains(&pattern.to_lowercase()))
+        };
+
+        by_address.or_else(by_name).map(|(_, icon)| icon.as_str())
+    }
+
+    /// The icon to show for a device, the one the user picked or else `icon`
+    pub fn device_icon<'a>(&'a self, addr: bluer::Address, name: &str, icon: &'a str) -> &'a str {
+        self.icon_override(addr, name).unwrap_or(icon)
+    }
+
+    /// When a device last connected, in seconds since the unix epoch
+    pub fn last_connected(&self, addr: bluer::Address) -> Option<u64> {
+        self.last_connected
+            .iter()
+            .find(|(a, _)| a.parse() == Ok(addr))
+            .map(|(_, time)| *time)
+    }
+
+    pub fn set_last_connected(&mut self, addr: bluer::Address, time: u64) {
+        match self
+            .last_connected
+            .iter_mut()
+            .find(|(a, _)| a.parse() == Ok(addr))
+        {
+            Some((_, t)) => *t = time,
+            None => self.last_connected.push((addr.to_string(), time)),
+        }
+    }
+
+    pub fn nickname(&self, addr: bluer::Address) -> Option<&str> {
+        self.nicknames
+            .iter()
+            .find(|(a, _)| a.parse() == Ok(addr))
+            .map(|(_, nickname)| nickname.as_str())
+    }
+
+    /// Sets the nickname of a device, an empty nickname removes it
+    pub fn set_nickname(&mut self, addr: bluer::Address, nickname: &str) {
+        self.nicknames.retain(|(a, _)| a.parse() != Ok(addr));
+        if !nickname.is_empty() {
+            self.nicknames
+                .push((addr.to_string(), nickname.to_string()));
+        }
+    }
+
+    /// The name to show for a device, its nickname if it has one
+    pub fn device_name<'a>(&'a self, addr: bluer::Address, name: &'a str) -> &'a str {
+        self.nickname(addr).unwrap_or(name)
+    }
+
+    /// The settings command, falling back to cosmic-settings if it was left blank
+    pub fn settings_command(&self) -> &str {
+        if self.settings_command.trim().is_empty() {
+            DEFAULT_SETTINGS_COMMAND
+        } else {
+            &self.settings_command
+        }
+    }
+
+    /// The connect limit, a limit of 0 would block every connect so it counts as 1
+    pub fn concurrent_connects(&self) -> usize {
+        self.concurrent_connects.max(1)
+    }
+
+    /// How long a connect may take, a timeout of 0 would fail every connect so it falls back to the default
+    pub fn connect_timeout(&self) -> Duration {
+        match self.connect_timeout_secs {
+            0 => Duration::from_secs(DEFAULT_CONNECT_TIMEOUT_SECS),
+            secs => Duration::from_secs(secs),
+        }
+    }
+
+    /// Connect retries, at least one attempt and no busy looping between attempts
+    pub fn retry_policy(&self) -> RetryPolicy {
+        let initial_backoff = Duration::from_millis(self.connect_initial_backoff_ms.max(50));
+        RetryPolicy {
+            max_tries: self.connect_max_tries.max(1),
+            initial_backoff,
+            max_backoff: Duration::from_millis(self.connect_max_backoff_ms).max(initial_backoff),
+        }
+    }
+
+    /// How long each background scan runs, at least a second
+    pub fn scan_duration(&self) -> Duration {
+        Duration::from_secs(self.scan_duration_secs.max(1))
+    }
+
+    /// The pause between background scans, at least a second
+    pub fn scan_interval(&self) -> Duration {
+        Duration::from_secs(self.scan_interval_secs.max(1))
+    }
+
+    /// Seconds the computer stays discoverable. bluez treats 0 as forever, which is no safe default, so it falls
+    /// back to the default
+    pub fn discoverable_timeout_secs(&self) -> u32 {
+        match self.discoverable_timeout_secs {
+            0 => DEFAULT_DISCOVERABLE_TIMEOUT_SECS,
+            secs => secs,
+        }
+    }
+
+    /// How long the popup scans for, `None` when it scans until closed
+    pub fn discovery_timeout(&self) -> Option<Duration> {
+        match self.discovery_timeout_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// The pause between battery reads, `None` when polling is turned off
+    pub fn battery_poll_interval(&self) -> Option<Duration> {
+        match self.battery_poll_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    /// Where a battery percentage falls between the thresholds. A low threshold below the caution threshold
+    /// counts as equal to it
+    pub fn battery_level(&self, percent: u8) -> BatteryLevel {
+        let caution = self.battery_caution_threshold.min(100);
+        if percent < caution {
+            BatteryLevel::Caution
+        } else if percent < self.battery_low_threshold.clamp(caution, 100) {
+            BatteryLevel::Low
+        } else {
+            BatteryLevel::Normal
+        }
+    }
+
+    /// The popup width, kept within sizes the popup layout still works at
+    pub fn popup_width(&self) -> Option<f32> {
+        self.popup_width.map(|w| w.clamp(280, 800) as f32)
+    }
+
+    /// The popup height, at least enough for the toggles and a few devices. The screen height caps it further
+    pub fn popup_height(&self) -> Option<f32> {
+        self.popup_height.map(|h| h.max(300) as f32)
+    }
+
+    /// The device list height, kept within sizes that fit a few rows and still fit on screen
+    pub fn list_max_height(&self) -> f32 {
+        self.list_max_height.clamp(150, 1000) as f32
+    }
+}
@@ -0,0 +1,134 @@
+// Copyright 2023 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+// Lets scripts connect and disconnect devices through the running applet, e.g.
+//
+//   busctl --user call com.system76.CosmicAppletBluetooth /com/system76/CosmicAppletBluetooth \
+//       com.system76.CosmicAppletBluetooth Connect s 00:11:22:33:44:55
+//
+// or `cosmic-applet-bluetooth connect 00:11:22:33:44:55`. `Connect(s address)` and `Disconnect(s address)` return
+// as soon as the applet has the request, without waiting for the device. A malformed address fails with
+// `org.freedesktop.DBus.Error.InvalidArgs`.
+
+use cosmic::iced_futures::stream;
+use futures::{SinkExt, Stream};
+use zbus::proxy;
+
+use crate::worker::WorkerRequest;
+
+const NAME: &str = "com.system76.CosmicAppletBluetooth";
+const OBJECT_PATH: &str = "/com/system76/CosmicAppletBluetooth";
+
+#[proxy(
+    interface = "com.system76.CosmicAppletBluetooth",
+    default_service = "com.system76.CosmicAppletBluetooth",
+    default_path = "/com/system76/CosmicAppletBluetooth"
+)]
+trait Applet {
+    fn connect(&self, address: &str) -> zbus::Result<()>;
+
+    fn disconnect(&self, address: &str) -> zbus::Result<()>;
+}
+
+struct AppletInterface {
+    output: futures::channel::mpsc::Sender<WorkerRequest>,
+}
+
+#[zbus::interface(name = "com.system76.CosmicAppletBluetooth")]
+impl AppletInterface {
+    async fn connect(&self, address: &str) -> zbus::fdo::Result<()> {
+        self.forward(address, WorkerRequest::ConnectDevice).await
+    }
+
+    async fn disconnect(&self, address: &str) -> zbus::fdo::Result<()> {
+        self.forward(address, WorkerRequest::DisconnectDevice).await
+    }
+}
+
+impl AppletInterface {
+    async fn forward(
+        &self,
+        address: &str,
+        request: fn(bluer::Address) -> WorkerRequest,
+    ) -> zbus::fdo::Result<()> {
+        let addr = address
+            .parse()
+            .map_err(|_| zbus::fdo::Error::InvalidArgs(format!("Invalid address {address}")))?;
+        self.output
+            .clone()
+            .send(request(addr))
+            .await
+            .map_err(|e| zbus::fdo::Error::Failed(e.to_string()))
+    }
+}
+
+/// Requests sent to the applet over D-Bus
+pub fn requests() -> impl Stream<Item = WorkerRequest> {
+    stream::channel(10, async move |output| {
+        let res: zbus::Result<()> = async {
+            // requests are served until the connection is dropped
+            let _connection = zbus::connection::Builder::session()?
+                .name(NAME)?
+                .serve_at(OBJECT_PATH, AppletInterface { output })?
+                .build()
+                .await?;
+            futures::future::pending::<()>().await;
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = res {
+            tracing::error!("could not serve the D-Bus interface: {e}");
+        }
+    })
+}
+
+/// Runs `connect <address>` or `disconnect <address>` against the running applet, returning the exit code: 0
+/// once the applet has the request, 1 if the applet can't be reached, and 2 for a malformed command. `None` when
+/// the arguments aren't a command, and the applet should start as usual
+pub fn run_command(args: &[String]) -> Option<i32> {
+    let [command, rest @ ..] = args else {
+        return None;
+    };
+    if command != "connect" && command != "disconnect" {
+        return None;
+    }
+
+    let [address] = rest else {
+        eprintln!("usage: cosmic-applet-bluetooth {command} <address>");
+        return Some(2);
+    };
+    if address.parse::<bluer::Address>().is_err() {
+        eprintln!("invalid bluetooth address: {address}");
+        return Some(2);
+    }
+
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("could not start: {e}");
+            return Some(1);
+        }
+    };
+
+    let res = runtime.block_on(async {
+        let connection = zbus::Connection::session().await?;
+        let proxy = AppletProxy::new(&connection).await?;
+        if command == "connect" {
+            proxy.connect(address).await
+        } else {
+            proxy.disconnect(address).await
+        }
+    });
+
+    match res {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("could not reach the bluetooth applet: {e}");
+            Some(1)
+        }
+    }
+}
@@ -1,15 +1,60 @@
+use std::collections::{HashMap, VecDeque};
+
 use futures::{FutureExt};
 
+use crate::worker::ConnectError;
+
+/// Handsfree and headset profiles, which bring up the microphone and drop audio to call quality
+pub const HEADSET_PROFILES: [bluer::Uuid; 2] = [
+    bluer::Uuid::from_u128(0x0000111e_0000_1000_8000_00805f9b34fb), // Handsfree
+    bluer::Uuid::from_u128(0x00001108_0000_1000_8000_00805f9b34fb), // Headset
+];
+
+/// GATT battery service, devices offering it can report their battery level even if they haven't yet
+pub const BATTERY_SERVICE: bluer::Uuid = bluer::Uuid::from_u128(0x0000180f_0000_1000_8000_00805f9b34fb);
+/// Battery level characteristic of the battery service
+pub const BATTERY_LEVEL: bluer::Uuid = bluer::Uuid::from_u128(0x00002a19_0000_1000_8000_00805f9b34fb);
+/// Descriptor naming a characteristic, e.g. which earbud a battery level belongs to
+pub const USER_DESCRIPTION: bluer::Uuid = bluer::Uuid::from_u128(0x00002901_0000_1000_8000_00805f9b34fb);
+
+/// How many signal strength samples are kept per connected device
+pub const SIGNAL_HISTORY_LEN: usize = 30;
+
 /// a mirror/cache of the bluer device struct, recieves updates from worker
 #[derive(Debug, Clone)]
 pub struct BluetoothDevice {
     pub icon: &'static str,
+    pub category: DeviceCategory,
     pub name: String,
     pub status: ConnectionStatus,
     pub battery_percent: Option<u8>,
     pub is_paired: bool,
+    pub is_trusted: bool,
+    /// bluez refuses any connection from or to a blocked device
+    pub is_blocked: bool,
     pub address: bluer::Address,
-    pub display_code: Option<String>,
+    pub rssi: Option<i16>,
+    /// recent rssi samples while connected, oldest first
+    pub signal_history: VecDeque<i16>,
+    /// whether the device may wake the computer from suspend, `None` if bluez doesn't expose it for this device
+    pub wake_allowed: Option<bool>,
+    /// when the applet asked to connect, for the timeout countdown
+    pub connecting_since: Option<std::time::Instant>,
+    /// when the device connected, or when the applet first saw it connected
+    pub connected_since: Option<std::time::Instant>,
+    /// why the last connect attempt failed, until the next one
+    pub connect_error: Option<ConnectError>,
+    /// the adapter the device is paired with, when it isn't the one in use
+    pub adapter: Option<String>,
+    /// the device offers a headset profile besides audio playback
+    pub supports_headset: bool,
+    /// classic profiles the device offers, which can be connected one at a time
+    pub profiles: Vec<bluer::Uuid>,
+    /// the device can report its battery level, whether or not it has so far
+    pub battery_supported: bool,
+    /// separately reported batteries, e.g. left and right earbud and the case. Empty when the device only has one,
+    /// an empty label means the device didn't name it
+    pub batteries: Vec<(String, u8)>,
 }
 
 #[derive(Debug, Clone)]
@@ -17,6 +62,18 @@ pub enum DeviceUpdate {
     Connected(bool),
     Battery(u8),
     Paired(bool),
+    Trusted(bool),
+    Blocked(bool),
+    Rssi(i16),
+    WakeAllowed(bool),
+    /// the displayed name, i.e. the alias, changed
+    Name(String),
+    /// the name the device reports for itself, which only replaces a placeholder address
+    DeviceName(String),
+    /// bluez learned the device type, usually right after pairing when the class is read
+    DeviceType(String),
+    /// levels of a device with several batteries
+    Batteries(Vec<(String, u8)>),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -27,6 +84,51 @@ pub enum ConnectionStatus {
     Disconnecting
 }
 
+/// Coarse grouping of devices, derived from the bluez device type. Grouped lists are in declaration order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DeviceCategory {
+    Audio,
+    Input,
+    Phone,
+    Computer,
+    Other,
+}
+
+impl DeviceCategory {
+    fn from_device_type(device_type: &str) -> Self {
+        match device_type {
+            "audio-headset" | "audio-headphones" | "audio-card" => Self::Audio,
+            "input-gaming" | "input-keyboard" | "input-tablet" | "input-mouse" => Self::Input,
+            "phone" => Self::Phone,
+            "computer" => Self::Computer,
+            _ => Self::Other,
+        }
+    }
+
+    /// The `category` selector of the `device-category` message
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Audio => "audio",
+            Self::Input => "input",
+            Self::Phone => "phone",
+            Self::Computer => "computer",
+            Self::Other => "other",
+        }
+    }
+
+    /// The category the user most likely wants to connect, judged by what they've paired before
+    pub fn most_used<'a>(paired: impl IntoIterator<Item = &'a BluetoothDevice>) -> Option<Self> {
+        let mut counts = HashMap::new();
+        for dev in paired {
+            if dev.category != Self::Other {
+                *counts.entry(dev.category).or_insert(0) += 1;
+            }
+        }
+
+        counts.into_iter().max_by_key(|(_, count)| *count).map(|(category, _)| category)
+    }
+}
+
 pub const DEFAULT_DEVICE_ICON: &'static str = "bluetooth-symbolic";
 
 // Copied from https://github.com/bluez/bluez/blob/39467578207889fd015775cbe81a3db9dd26abea/src/dbus-common.c#L53
@@ -45,22 +147,36 @@ fn device_type_to_icon(device_type: &str) -> &'static str {
         "input-mouse" => "input-mouse-symbolic",
         "printer" => "printer-network-symbolic",
         "camera-photo" => "camera-photo-symbolic",
-        _ => "bluetooth-symbolic",
+        _ => DEFAULT_DEVICE_ICON,
+    }
+}
+
+/// Human readable name of a service or profile uuid
+pub fn service_name(uuid: bluer::Uuid) -> String {
+    if let Ok(class) = bluer::id::ServiceClass::try_from(uuid) {
+        class.to_string()
+    } else if let Ok(service) = bluer::id::Service::try_from(uuid) {
+        service.to_string()
+    } else {
+        uuid.to_string()
     }
 }
 
 impl BluetoothDevice {
     pub async fn from_device(device: &bluer::Device) -> Self {
         let (
-        mut name, is_paired, _is_trusted, is_connected, battery_percent, icon) = futures::join!(
-            device.name().map(|res| res.ok().flatten().unwrap_or_default()),
+        mut name, is_paired, is_trusted, is_blocked, is_connected, battery_percent, rssi, wake_allowed, device_type, uuids) = futures::join!(
+            // the alias is the user's custom name, bluez falls back to the device's own name when none is set
+            device.alias().map(|res| res.unwrap_or_default()),
             device.is_paired().map(Result::unwrap_or_default),
             device.is_trusted().map(Result::unwrap_or_default),
+            device.is_blocked().map(Result::unwrap_or_default),
             device.is_connected().map(Result::unwrap_or_default),
             device.battery_percentage().map(|res| res.ok().flatten()),
-            device
-                .icon()
-                .map(|res| device_type_to_icon(&res.ok().flatten().unwrap_or_default()))
+            device.rssi().map(|res| res.ok().flatten()),
+            device.wake_allowed().map(Result::ok),
+            device.icon().map(|res| res.ok().flatten().unwrap_or_default()),
+            device.uuids().map(|res| res.ok().flatten().unwrap_or_default())
         );
 
         if name.is_empty() {
@@ -75,26 +191,180 @@ impl BluetoothDevice {
 
         Self {
             name,
-            icon,
+            icon: device_type_to_icon(&device_type),
+            category: DeviceCategory::from_device_type(&device_type),
             status,
             battery_percent,
             is_paired,
+            is_trusted,
+            is_blocked,
             address: device.address(),
-            display_code: None,
+            rssi,
+            signal_history: VecDeque::new(),
+            wake_allowed,
+            connecting_since: None,
+            connected_since: is_connected.then(std::time::Instant::now),
+            connect_error: None,
+            adapter: None,
+            supports_headset: HEADSET_PROFILES.iter().any(|uuid| uuids.contains(uuid)),
+            // gatt services are listed alongside, but only profiles can be connected
+            profiles: uuids
+                .iter()
+                .copied()
+                .filter(|uuid| bluer::id::ServiceClass::try_from(*uuid).is_ok())
+                .collect(),
+            battery_supported: battery_percent.is_some() || uuids.contains(&BATTERY_SERVICE),
+            batteries: Vec::new(),
+        }
+    }
+
+    /// Whether the update connects a disconnected device or drops a connected one. The status can't tell, it is
+    /// already `Connecting` or `Disconnecting` while the applet waits on bluez
+    pub fn is_connection_change(&self, update: &DeviceUpdate) -> bool {
+        matches!(
+            update,
+            DeviceUpdate::Connected(connected) if *connected != self.connected_since.is_some()
+        )
+    }
+
+    /// Carries over what only the applet knows about a device that was read again from bluez
+    pub fn keep_app_state(&mut self, old: &BluetoothDevice) {
+        // bluez only knows the device is connected, not since when
+        if self.connected_since.is_some() && old.connected_since.is_some() {
+            self.connected_since = old.connected_since;
+            self.signal_history = old.signal_history.clone();
+        }
+        // a connect the applet started is still running
+        if matches!(old.status, ConnectionStatus::Connecting)
+            && matches!(self.status, ConnectionStatus::Disconnected)
+        {
+            self.status = ConnectionStatus::Connecting;
+            self.connecting_since = old.connecting_since;
         }
+        self.connect_error = old.connect_error.clone();
+    }
+
+    /// Whether the device matches a search, by name or address. `filter` must already be lowercase.
+    pub fn matches(&self, filter: &str) -> bool {
+        self.name.to_lowercase().contains(filter)
+            || self.address.to_string().to_lowercase().contains(filter)
+    }
+
+    /// Whether the device has nothing but its address for a name. bluez uses the address as the alias of nameless
+    /// devices, written with dashes
+    pub fn is_unnamed(&self) -> bool {
+        let address = self.address.to_string();
+        self.name == address || self.name == address.replace(':', "-")
     }
 
     pub fn handle_device_updates(&mut self, update: DeviceUpdate) {
         match update {
-            DeviceUpdate::Battery(battery) => self.battery_percent = Some(battery),
+            DeviceUpdate::Battery(battery) => {
+                self.battery_percent = Some(battery);
+                self.battery_supported = true;
+            }
             DeviceUpdate::Paired(paired) => self.is_paired = paired,
+            DeviceUpdate::Trusted(trusted) => self.is_trusted = trusted,
+            DeviceUpdate::Blocked(blocked) => self.is_blocked = blocked,
+            DeviceUpdate::WakeAllowed(allowed) => self.wake_allowed = Some(allowed),
+            DeviceUpdate::DeviceType(device_type) => {
+                self.icon = device_type_to_icon(&device_type);
+                self.category = DeviceCategory::from_device_type(&device_type);
+            }
+            DeviceUpdate::Batteries(batteries) => {
+                self.battery_supported |= !batteries.is_empty();
+                self.batteries = batteries;
+            }
+            DeviceUpdate::Name(name) => {
+                self.name = if name.is_empty() {
+                    self.address.to_string()
+                } else {
+                    name
+                }
+            }
+            // found devices often send their name a moment after being added, and bluez doesn't always update
+            // an alias it derived from the address
+            DeviceUpdate::DeviceName(name) => {
+                if !name.is_empty() && self.is_unnamed() {
+                    self.name = name;
+                }
+            }
+            DeviceUpdate::Rssi(rssi) => {
+                self.rssi = Some(rssi);
+
+                if matches!(self.status, ConnectionStatus::Connected) {
+                    if self.signal_history.len() == SIGNAL_HISTORY_LEN {
+                        self.signal_history.pop_front();
+                    }
+                    self.signal_history.push_back(rssi);
+                }
+            }
             DeviceUpdate::Connected(connected) => {
+                self.connecting_since = None;
+                self.connect_error = None;
                 self.status = if connected {
+                    // bluez repeats the property, the first report is when it connected
+                    self.connected_since.get_or_insert_with(std::time::Instant::now);
                     ConnectionStatus::Connected
                 } else {
+                    self.signal_history.clear();
+                    self.connected_since = None;
                     ConnectionStatus::Disconnected
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(status: ConnectionStatus, connected: bool) -> BluetoothDevice {
+        BluetoothDevice {
+            icon: "bluetooth-symbolic",
+            category: DeviceCategory::Other,
+            name: String::new(),
+            status,
+            battery_percent: None,
+            is_paired: true,
+            is_trusted: false,
+            is_blocked: false,
+            address: bluer::Address::new([1, 2, 3, 4, 5, 6]),
+            rssi: None,
+            signal_history: VecDeque::new(),
+            wake_allowed: None,
+            connecting_since: None,
+            connected_since: connected.then(std::time::Instant::now),
+            connect_error: None,
+            adapter: None,
+            supports_headset: false,
+            profiles: Vec::new(),
+            battery_supported: false,
+            batteries: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn user_disconnect_is_a_connection_change() {
+        // Disconnect and DisconnectAll mark the device before bluez reports it gone
+        let dev = device(ConnectionStatus::Disconnecting, true);
+        assert!(dev.is_connection_change(&DeviceUpdate::Connected(false)));
+        // the disconnect failed and the device stayed connected
+        assert!(!dev.is_connection_change(&DeviceUpdate::Connected(true)));
+    }
+
+    #[test]
+    fn failed_connect_is_not_a_connection_change() {
+        let dev = device(ConnectionStatus::Connecting, false);
+        assert!(!dev.is_connection_change(&DeviceUpdate::Connected(false)));
+        assert!(dev.is_connection_change(&DeviceUpdate::Connected(true)));
+    }
+
+    #[test]
+    fn repeated_property_is_not_a_connection_change() {
+        let dev = device(ConnectionStatus::Connected, true);
+        assert!(!dev.is_connection_change(&DeviceUpdate::Connected(true)));
+        assert!(!dev.is_connection_change(&DeviceUpdate::Battery(50)));
+    }
+}
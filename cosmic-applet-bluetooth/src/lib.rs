@@ -4,12 +4,16 @@
 mod app;
 mod config;
 mod localize;
+mod notification;
 mod device;
 mod worker;
 mod agent;
+mod dbus;
 
 use crate::localize::localize;
 
+pub use dbus::run_command;
+
 #[inline]
 pub fn run() -> cosmic::iced::Result {
     localize();
@@ -7,6 +7,12 @@ fn main() -> cosmic::iced::Result {
     tracing_subscriber::fmt::init();
     let _ = tracing_log::LogTracer::init();
 
+    // `connect <address>` and `disconnect <address>` are passed to the running applet instead of starting one
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cosmic_applet_bluetooth::run_command(&args) {
+        std::process::exit(code);
+    }
+
     tracing::info!("Starting bluetooth applet with version {VERSION}");
 
     cosmic_applet_bluetooth::run()
@@ -0,0 +1,140 @@
+// Copyright 2023 System76 <info@system76.com>
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::collections::HashMap;
+
+use cosmic::iced_futures::stream;
+use futures::{SinkExt, Stream, StreamExt};
+use tokio::sync::OnceCell;
+use zbus::{proxy, zvariant::Value};
+
+use crate::fl;
+
+#[proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    /// CloseNotification method
+    fn close_notification(&self, id: u32) -> zbus::Result<()>;
+
+    /// GetCapabilities method
+    fn get_capabilities(&self) -> zbus::Result<Vec<String>>;
+
+    /// Notify method
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    /// ActionInvoked signal
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: &str) -> zbus::Result<()>;
+}
+
+static CONNECTION: OnceCell<zbus::Connection> = OnceCell::const_new();
+
+async fn proxy() -> zbus::Result<NotificationsProxy<'static>> {
+    let connection = CONNECTION
+        .get_or_try_init(zbus::Connection::session)
+        .await?;
+    NotificationsProxy::new(connection).await
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub summary: String,
+    pub body: String,
+    pub icon: String,
+    /// (key, label) pairs, the key is reported back through [`actions`] when clicked
+    pub actions: Vec<(&'static str, String)>,
+    pub urgent: bool,
+}
+
+impl Notification {
+    pub fn new(summary: String, body: String, icon: impl Into<String>) -> Self {
+        Self {
+            summary,
+            body,
+            icon: icon.into(),
+            actions: Vec::new(),
+            urgent: false,
+        }
+    }
+}
+
+/// Shows the notification, returning its id. If the notification server can't show actions, the body asks the
+/// user to open the applet instead
+pub async fn send(notification: Notification) -> zbus::Result<u32> {
+    let proxy = proxy().await?;
+
+    let mut body = notification.body;
+    let mut actions = Vec::new();
+    if !notification.actions.is_empty() {
+        if proxy
+            .get_capabilities()
+            .await?
+            .iter()
+            .any(|c| c == "actions")
+        {
+            actions = notification
+                .actions
+                .iter()
+                .flat_map(|(key, label)| [*key, label.as_str()])
+                .collect();
+        } else {
+            body = format!("{body}\n{}", fl!("open-applet"));
+        }
+    }
+
+    let mut hints = HashMap::new();
+    if notification.urgent {
+        hints.insert("urgency", Value::U8(2));
+    }
+
+    proxy
+        .notify(
+            &fl!("bluetooth"),
+            0,
+            &notification.icon,
+            &notification.summary,
+            &body,
+            &actions,
+            hints,
+            -1,
+        )
+        .await
+}
+
+pub async fn close(id: u32) -> zbus::Result<()> {
+    proxy().await?.close_notification(id).await
+}
+
+/// Notification id and action key for every notification action the user clicks
+pub fn actions() -> impl Stream<Item = (u32, String)> {
+    stream::channel(10, async move |mut output| {
+        let res: zbus::Result<()> = async {
+            let mut invoked = proxy().await?.receive_action_invoked().await?;
+
+            while let Some(signal) = invoked.next().await {
+                let args = signal.args()?;
+                _ = output.send((args.id, args.action_key.to_string())).await;
+            }
+
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = res {
+            tracing::error!("could not listen for notification actions: {e}");
+        }
+    })
+}
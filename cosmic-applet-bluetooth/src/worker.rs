@@ -1,42 +1,315 @@
 use std::{
-    collections::HashMap,
-    io::Write,
+    collections::{HashMap, HashSet},
+    io::{Read, Write},
+    future::Future,
+    path::Path,
     pin::{Pin, pin},
+    sync::{Arc, Mutex},
     time::Duration,
 };
 
 use anyhow::{Context, bail};
 use cosmic::iced_futures::stream;
 
-use bluer::{AdapterEvent, AdapterProperty, DeviceEvent, DeviceProperty};
+use bluer::{AdapterEvent, AdapterProperty, DeviceEvent, DeviceProperty, SessionEvent};
 use futures::{FutureExt, SinkExt, Stream, StreamExt, TryStreamExt, stream::FuturesUnordered};
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{Semaphore, mpsc, oneshot};
 
-use crate::{agent::{AgentEvent, create_agent}, device::{BluetoothDevice, DEFAULT_DEVICE_ICON, DeviceUpdate}};
+use crate::{agent::{AgentEvent, create_agent}, config::{BluetoothAppletConfig, DisableMode, RetryPolicy}, device::{BATTERY_LEVEL, BATTERY_SERVICE, BluetoothDevice, DeviceUpdate, HEADSET_PROFILES, USER_DESCRIPTION, service_name}};
+
+/// Information about the local adapter
+#[derive(Debug, Clone)]
+pub struct AdapterInfo {
+    pub name: String,
+    pub address: bluer::Address,
+    /// the name other devices see
+    pub alias: String,
+}
+
+impl AdapterInfo {
+    async fn read(adapter: &bluer::Adapter) -> anyhow::Result<Self> {
+        Ok(Self {
+            name: adapter.name().to_string(),
+            address: adapter.address().await?,
+            alias: adapter.alias().await?,
+        })
+    }
+}
+
+/// Technical details of a device, read only when the user asks for them
+#[derive(Debug, Clone)]
+pub struct DeviceInfo {
+    pub address_type: String,
+    /// class of device, which classic devices report
+    pub class: Option<u32>,
+    /// GAP appearance, which low energy devices report
+    pub appearance: Option<u16>,
+    /// services the device offers, sorted by name
+    pub services: Vec<bluer::Uuid>,
+    /// (vendor id, product id) from the device's modalias
+    pub vendor_product: Option<(u32, u32)>,
+}
+
+impl DeviceInfo {
+    async fn read(device: &bluer::Device) -> anyhow::Result<Self> {
+        let mut services: Vec<_> = device.uuids().await?.unwrap_or_default().into_iter().collect();
+        services.sort_by_key(|uuid| service_name(*uuid));
+
+        Ok(Self {
+            address_type: device.address_type().await?.to_string(),
+            class: device.class().await?,
+            appearance: device.appearance().await?,
+            services,
+            vendor_product: device.modalias().await?.map(|m| (m.vendor, m.product)),
+        })
+    }
+}
+
+/// Why a connect failed, in terms a user can act on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectErrorKind {
+    Authentication,
+    Timeout,
+    ProfileUnavailable,
+    OutOfRange,
+    Busy,
+    Other,
+}
+
+impl ConnectErrorKind {
+    /// The `kind` selector of the `connect-error` message
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Authentication => "authentication",
+            Self::Timeout => "timeout",
+            Self::ProfileUnavailable => "profile",
+            Self::OutOfRange => "range",
+            Self::Busy => "busy",
+            Self::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConnectError {
+    pub kind: ConnectErrorKind,
+    /// the raw error, for users who want to dig further
+    pub details: String,
+}
+
+impl ConnectError {
+    fn from_error(err: &anyhow::Error) -> Self {
+        Self {
+            kind: connect_error_kind(err),
+            details: format!("{err:#}"),
+        }
+    }
+}
+
+fn connect_error_kind(err: &anyhow::Error) -> ConnectErrorKind {
+    use bluer::ErrorKind;
+
+    if err.downcast_ref::<tokio::time::error::Elapsed>().is_some() {
+        return ConnectErrorKind::Timeout;
+    }
+
+    let Some(err) = err.chain().find_map(|e| e.downcast_ref::<bluer::Error>()) else {
+        return ConnectErrorKind::Other;
+    };
+
+    match &err.kind {
+        ErrorKind::AuthenticationCanceled
+        | ErrorKind::AuthenticationFailed
+        | ErrorKind::AuthenticationRejected
+        | ErrorKind::AuthenticationTimeout
+        | ErrorKind::NotAuthorized => ConnectErrorKind::Authentication,
+        ErrorKind::ConnectionAttemptFailed => ConnectErrorKind::OutOfRange,
+        ErrorKind::InProgress | ErrorKind::AlreadyConnected => ConnectErrorKind::Busy,
+        ErrorKind::NotAvailable | ErrorKind::NotSupported => ConnectErrorKind::ProfileUnavailable,
+        // bluez reports most connect failures as a generic failure with a descriptive message
+        _ => match err.message.as_str() {
+            m if m.contains("profile-unavailable") => ConnectErrorKind::ProfileUnavailable,
+            m if m.contains("page-timeout") || m.contains("Host is down") => {
+                ConnectErrorKind::OutOfRange
+            }
+            m if m.contains("busy") || m.contains("In Progress") => ConnectErrorKind::Busy,
+            m if m.contains("timeout") || m.contains("Timeout") => ConnectErrorKind::Timeout,
+            _ => ConnectErrorKind::Other,
+        },
+    }
+}
 
 #[derive(Debug, Clone)]
 pub enum WorkerEvent {
     Ready(mpsc::UnboundedSender<WorkerRequest>, bool),
+    AdapterInfo(AdapterInfo),
+    /// names of all adapters on the system
+    Adapters(Vec<String>),
+    /// the complete device list, replacing whatever was sent before
     DeviceMap(HashMap<bluer::Address, BluetoothDevice>),
+    /// also sent for each device while the device list is built, so long lists show up progressively
     DeviceAdded(BluetoothDevice),
     DeviceRemoved(bluer::Address),
-    ConnectFailed(bluer::Address),
+    /// details of a device, in reply to `FetchDetails`
+    DeviceDetails(bluer::Address, DeviceInfo),
+    /// the error is `None` when the user cancelled the connect
+    ConnectFailed(bluer::Address, Option<ConnectError>),
     DeviceUpdate(bluer::Address, DeviceUpdate),
     Enabled(bool),
     Error(String),
+    /// something failed that the worker recovered from, but the user should hear about
+    Warning(String),
+    /// turning bluetooth on or off failed, so no `Enabled` follows
+    PowerFailed,
+    /// rebuilding the device list failed, so no `DeviceMap` follows
+    RefreshFailed,
     ConfirmCode(String, bluer::Address),
+    /// the user didn't answer the pairing code in time, so the pairing was rejected
+    ConfirmationExpired(bluer::Address),
+    /// the device wants a PIN typed in to pair
+    RequestPinCode(bluer::Address),
+    /// a code for the user to type on the device, with how many digits they've typed so far when known
+    DisplayPasskey(bluer::Address, String, Option<u16>),
+    /// the device asks to use a service, or to pair without a code when the service is `None`
+    AuthorizeService(bluer::Address, Option<bluer::Uuid>),
+    /// the connection to bluez dropped and the worker is re-establishing it
+    Reconnecting,
+    /// there is no adapter, the worker is waiting for one to be plugged in
+    NoAdapter,
+    /// whether other devices can see this computer, and for how long if it turns itself off
+    Discoverable(bool, Option<Duration>),
+    /// whether incoming pairing requests are accepted
+    Pairable(bool),
+    /// a hardware switch or airplane mode blocks the radio, so it can't be turned on from here
+    HardBlocked(bool),
+    /// the radio is soft-blocked through rfkill, by airplane mode or another tool
+    SoftBlocked(bool),
+    /// the popup's scan ran for the configured time and was stopped
+    DiscoveryTimedOut,
+    /// whether the adapter is actually scanning, which may lag behind or ignore a request to scan
+    Discovering(bool),
 }
 
 #[derive(Debug, Clone)]
 pub enum WorkerRequest {
     SetDiscovery(bool),
     ConnectDevice(bluer::Address),
+    /// pair the device without connecting it, the agent still handles any confirmation or PIN
+    PairDevice(bluer::Address),
     DisconnectDevice(bluer::Address),
+    DisconnectAll,
+    /// let other devices find this computer, for the configured timeout
+    SetDiscoverable(bool),
+    SetPairable(bool),
     CancelConnect(bluer::Address),
+    /// disconnect then connect again, for devices that are connected but not responding
+    ReconnectDevice(bluer::Address),
     SetEnabled(bool),
+    /// soft-block the radio through rfkill, which unlike powering the adapter down survives power cycling
+    SetAirplane(bool),
+    /// accept or reject a pending pairing, rejecting also cancels a pending PIN request
     ConfirmCode(bluer::Address, bool),
+    SubmitPinCode(bluer::Address, String),
+    AuthorizeService(bluer::Address, bool),
+    /// rebuild the device list from scratch and resend it
+    RefreshDevices,
+    /// resend a single device, for an app that heard of it before getting the device itself
+    FetchDevice(bluer::Address),
+    SetConfig(BluetoothAppletConfig),
+    /// allow or forbid an input device from waking the computer
+    SetWakeAllowed(bluer::Address, bool),
+    /// unpair the device and remove it from the adapter
+    RemoveDevice(bluer::Address),
+    /// connect or drop the headset profile of a connected audio device, to match a changed preference
+    SetHeadsetProfile(bluer::Address, bool),
+    /// give the device a custom name, an empty alias resets it to the device's own name
+    SetAlias(bluer::Address, String),
+    /// trusted devices may connect without asking, which lets them reconnect automatically
+    SetTrusted(bluer::Address, bool),
+    /// blocked devices can't connect at all, bluez disconnects them when they're blocked
+    SetBlocked(bluer::Address, bool),
+    /// switch to another adapter, by name
+    SelectAdapter(String),
+    /// connect a single profile of the device, e.g. only music playback of a headset
+    ConnectProfile(bluer::Address, bluer::Uuid),
+    /// set the name other devices see this computer by, an empty alias resets it to the system's name
+    SetAdapterAlias(String),
+    /// read the device's technical details, which the device list leaves out
+    FetchDetails(bluer::Address),
+}
+
+impl WorkerRequest {
+    /// Whether the request can be repeated safely after reconnecting to bluez
+    fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::SetDiscovery(_)
+                | Self::ConnectDevice(_)
+                | Self::DisconnectDevice(_)
+                | Self::DisconnectAll
+                | Self::SetDiscoverable(_)
+                | Self::SetPairable(_)
+                | Self::ReconnectDevice(_)
+                | Self::SetEnabled(_)
+                | Self::SetAirplane(_)
+                | Self::RefreshDevices
+                | Self::FetchDevice(_)
+                | Self::SetWakeAllowed(..)
+                | Self::SetAlias(..)
+                | Self::SetTrusted(..)
+                | Self::SetBlocked(..)
+                | Self::SelectAdapter(_)
+                | Self::SetAdapterAlias(_)
+                | Self::FetchDetails(_)
+        )
+    }
 }
 
+/// D-Bus errors meaning the bus or bluetoothd went away, rather than the operation itself failing
+const DISCONNECTED_ERRORS: &[&str] = &[
+    "org.freedesktop.DBus.Error.Disconnected",
+    "org.freedesktop.DBus.Error.NoReply",
+    "org.freedesktop.DBus.Error.ServiceUnknown",
+    "org.freedesktop.DBus.Error.NameHasNoOwner",
+];
+
+fn is_disconnected(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        // bluer keeps the name of D-Bus errors it doesn't know as an internal error kind
+        matches!(
+            cause.downcast_ref::<bluer::Error>().map(|e| &e.kind),
+            Some(bluer::ErrorKind::Internal(bluer::InternalErrorKind::DBus(name)))
+                if DISCONNECTED_ERRORS.contains(&name.as_str())
+        )
+    })
+}
+
+/// How often the signal strength of connected devices is sampled
+const LINK_QUALITY_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long a pairing code waits for the user before the pairing is rejected
+pub const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long chatty device updates are collected before they're sent on, so a busy scan doesn't flood the app
+const UPDATE_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// How often reconnecting to bluez is tried after the connection dropped during a request
+const RECONNECT_RETRY: RetryPolicy = RetryPolicy {
+    max_tries: 4,
+    initial_backoff: Duration::from_millis(500),
+    max_backoff: Duration::from_secs(4),
+};
+
+/// How long to wait before recreating a failed worker, doubling up to the max
+const RESTART_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(60);
+/// How long a worker has to stay up before a restart starts again at the shortest backoff
+const RESTART_BACKOFF_RESET: Duration = Duration::from_secs(30);
+
+/// Size of the original `struct rfkill_event`, newer kernels still accept writes of this size
+const RFKILL_EVENT_SIZE_V1: usize = 8;
+const RFKILL_TYPE_BLUETOOTH: u8 = 2;
+
 // we need to use rfkill to enable/disable bluetooth
 #[repr(C, packed)]
 struct RfkillEvent {
@@ -47,20 +320,94 @@ struct RfkillEvent {
     hard: u8,
 }
 
+// the layout of the kernel's `struct rfkill_event`, `to_bytes` and `from_bytes` follow the same offsets
+const _: () = {
+    assert!(std::mem::size_of::<RfkillEvent>() == RFKILL_EVENT_SIZE_V1);
+    assert!(std::mem::offset_of!(RfkillEvent, idx) == 0);
+    assert!(std::mem::offset_of!(RfkillEvent, _type) == 4);
+    assert!(std::mem::offset_of!(RfkillEvent, op) == 5);
+    assert!(std::mem::offset_of!(RfkillEvent, soft) == 6);
+    assert!(std::mem::offset_of!(RfkillEvent, hard) == 7);
+};
+
+impl RfkillEvent {
+    /// Serializes the event the way the kernel reads it, the index is in host byte order
+    fn to_bytes(&self) -> [u8; RFKILL_EVENT_SIZE_V1] {
+        let mut bytes = [0; RFKILL_EVENT_SIZE_V1];
+        bytes[..4].copy_from_slice(&self.idx.to_ne_bytes());
+        bytes[4] = self._type;
+        bytes[5] = self.op;
+        bytes[6] = self.soft;
+        bytes[7] = self.hard;
+        bytes
+    }
+
+    fn from_bytes(bytes: [u8; RFKILL_EVENT_SIZE_V1]) -> Self {
+        Self {
+            idx: u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            _type: bytes[4],
+            op: bytes[5],
+            soft: bytes[6],
+            hard: bytes[7],
+        }
+    }
+}
+
 /// background worker struct, All calls to bluer and async code lives here
 /// listens for requests from the model, events from the adapter, and events for each of the devices
 struct BluetoothWorker {
     output: futures::channel::mpsc::Sender<WorkerEvent>,
     requests: mpsc::UnboundedReceiver<WorkerRequest>,
+    config: BluetoothAppletConfig,
+    session: bluer::Session,
     adapter: bluer::Adapter,
     adapter_events: Pin<Box<dyn Stream<Item = bluer::AdapterEvent> + Send>>,
+    /// adapters being plugged in and out
+    session_events: Pin<Box<dyn Stream<Item = SessionEvent> + Send>>,
+    /// the adapter in use was unplugged and there's no other, the worker stops and a new one waits for an adapter
+    adapter_removed: bool,
     discovery_events: Option<Pin<Box<dyn Stream<Item = bluer::AdapterEvent> + Send>>>,
     device_rx: mpsc::UnboundedReceiver<(bluer::Address, DeviceUpdate)>,
     device_tx: mpsc::UnboundedSender<(bluer::Address, DeviceUpdate)>,
     device_handles: HashMap<bluer::Address, tokio::task::JoinHandle<()>>,
     agent_handle: bluer::agent::AgentHandle,
     agent_rx: mpsc::UnboundedReceiver<AgentEvent>,
-    confirmation_senders: HashMap<bluer::Address, oneshot::Sender<bool>>,
+    /// pending pairing confirmations, and when each is rejected if the user doesn't answer
+    confirmation_senders: HashMap<bluer::Address, (oneshot::Sender<bool>, tokio::time::Instant)>,
+    /// fires at the earliest confirmation deadline
+    confirmation_timeout: Pin<Box<tokio::time::Sleep>>,
+    pin_code_senders: HashMap<bluer::Address, oneshot::Sender<String>>,
+    passkey_senders: HashMap<bluer::Address, oneshot::Sender<u32>>,
+    authorization_senders: HashMap<bluer::Address, oneshot::Sender<bool>>,
+    /// devices in the middle of a reconnect cycle, their disconnect is expected and not forwarded
+    reconnecting: Arc<Mutex<HashSet<bluer::Address>>>,
+    link_quality_poll: tokio::time::Interval,
+    /// debounced device updates waiting to be sent, only the latest of each kind is kept
+    pending_updates: HashMap<bluer::Address, Vec<DeviceUpdate>>,
+    /// when the pending updates are sent
+    flush_updates: Pin<Box<tokio::time::Sleep>>,
+    /// rfkill state changes, as the radio index and whether it is soft and hard blocked
+    rfkill_events: Pin<Box<dyn Stream<Item = (u32, bool, bool)> + Send>>,
+    /// rfkill index of the adapter's radio, looked up again after a resync
+    rfkill_idx: Option<u32>,
+    /// reads the battery of connected devices, `None` when turned off in the config
+    battery_poll: Option<tokio::time::Interval>,
+    /// the last level each device reported, so the poll only forwards changes
+    battery_levels: HashMap<bluer::Address, u8>,
+    /// every connect holds a permit while it runs, so the adapter isn't flooded with simultaneous connects
+    connect_limit: Arc<Semaphore>,
+    /// the popup is open and wants discovery running, this overrides the background scan cycle
+    popup_discovery: bool,
+    /// when the background scan next starts or stops
+    scan_cycle: Pin<Box<tokio::time::Sleep>>,
+    /// when the popup's scan is stopped
+    discovery_timeout: Pin<Box<tokio::time::Sleep>>,
+    /// devices listed from other adapters, and the adapter each belongs to
+    foreign_devices: HashMap<bluer::Address, String>,
+    /// devices with a connect running in the background, so they aren't connected twice at once
+    connecting: Arc<Mutex<HashSet<bluer::Address>>>,
+    /// devices that were connected when bluetooth was last turned off
+    connected_before_disable: HashSet<bluer::Address>,
 }
 
 impl BluetoothWorker {
@@ -69,28 +416,59 @@ impl BluetoothWorker {
     ) -> anyhow::Result<Self> {
         let (tx, rx) = mpsc::unbounded_channel();
 
-        let (adapter, session) = get_connection().await?;
+        let config = BluetoothAppletConfig::load();
+        let (adapter, session) = get_connection(&mut output, config.selected_adapter.as_deref()).await?;
 
         let (agent_tx, agent_rx) = mpsc::unbounded_channel();
         let agent = create_agent(agent_tx);
         let agent_handle = session.register_agent(agent).await?;
 
         let adapter_events = adapter.events().await?.boxed();
+        let session_events = session.events().await?.boxed();
 
         let (device_tx, device_rx) = mpsc::unbounded_channel();
 
-        let (bt_device_map, device_handles) = create_device_maps(&adapter, &device_tx).await?;
-
+        let battery_poll = battery_poll(&config);
+        // closes /dev/rfkill along with the worker
+        let rfkill_events = match rfkill_events() {
+            Ok(events) => events.boxed(),
+            Err(e) => {
+                tracing::warn!("could not watch rfkill: {e}");
+                futures::stream::pending().boxed()
+            }
+        };
         let enabled = adapter.is_powered().await?;
+        let adapter_info = AdapterInfo::read(&adapter).await?;
+        let adapters = session.adapter_names().await?;
 
+        // the adapter state goes out first, so the popup is usable while the devices are read
         _ = output.send(WorkerEvent::Ready(tx, enabled)).await;
+        _ = output.send(WorkerEvent::AdapterInfo(adapter_info)).await;
+        _ = output.send(discoverable_event(&adapter).await?).await;
+        _ = output.send(WorkerEvent::Pairable(adapter.is_pairable().await?)).await;
+        _ = output.send(WorkerEvent::Discovering(adapter.is_discovering().await?)).await;
+        _ = output.send(WorkerEvent::Adapters(adapters)).await;
+
+        let (mut bt_device_map, mut device_handles) = create_device_maps(&adapter, &device_tx, &mut output).await?;
+
+        let foreign_devices = if config.show_all_adapters {
+            add_foreign_devices(&session, &adapter, &device_tx, &mut bt_device_map, &mut device_handles).await?
+        } else {
+            HashMap::new()
+        };
+
         _ = output.send(WorkerEvent::DeviceMap(bt_device_map)).await;
 
         Ok(BluetoothWorker {
             output,
             requests: rx,
+            connect_limit: Arc::new(Semaphore::new(config.concurrent_connects())),
+            config,
+            session,
             adapter,
             adapter_events,
+            session_events,
+            adapter_removed: false,
             discovery_events: None,
             device_handles,
             device_rx,
@@ -98,21 +476,234 @@ impl BluetoothWorker {
             agent_handle,
             agent_rx,
             confirmation_senders: HashMap::new(),
+            confirmation_timeout: Box::pin(tokio::time::sleep(Duration::ZERO)),
+            pin_code_senders: HashMap::new(),
+            passkey_senders: HashMap::new(),
+            authorization_senders: HashMap::new(),
+            reconnecting: Arc::default(),
+            link_quality_poll: {
+                let mut interval = tokio::time::interval(LINK_QUALITY_POLL_INTERVAL);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                interval
+            },
+            battery_poll,
+            rfkill_events,
+            rfkill_idx: None,
+            pending_updates: HashMap::new(),
+            flush_updates: Box::pin(tokio::time::sleep(Duration::ZERO)),
+            battery_levels: HashMap::new(),
+            popup_discovery: false,
+            scan_cycle: Box::pin(tokio::time::sleep(Duration::ZERO)),
+            discovery_timeout: Box::pin(tokio::time::sleep(Duration::ZERO)),
+            foreign_devices,
+            connecting: Arc::default(),
+            connected_before_disable: HashSet::new(),
         })
     }
 
     async fn run(mut self) {
         loop {
-            if let Err(e) = self.listen().await {
-                _ = self.output.send(WorkerEvent::Error(format!{"{:?}", e})).await;
+            let res = self.listen().await;
+            if self.adapter_removed {
                 return;
             }
+            let Err(mut e) = res else {
+                continue;
+            };
+
+            if is_disconnected(&e) {
+                tracing::warn!("lost connection to bluez, reconnecting: {e:?}");
+                match self.reconnect().await {
+                    Ok(()) => continue,
+                    Err(reconnect_err) => e = reconnect_err,
+                }
+            }
+
+            _ = self.output.send(WorkerEvent::Error(format!{"{:?}", e})).await;
+            return;
+        }
+    }
+
+    /// Opens a new bluez session and rebuilds everything that hung off the old one
+    async fn reconnect(&mut self) -> anyhow::Result<()> {
+        _ = self.output.send(WorkerEvent::Reconnecting).await;
+
+        let was_discovering = self.discovery_events.take().is_some();
+        // pending pairings belonged to the old agent, dropping the senders rejects them
+        self.confirmation_senders.clear();
+        self.pin_code_senders.clear();
+        self.passkey_senders.clear();
+        self.authorization_senders.clear();
+
+        let (default_adapter, session) = get_connection(&mut self.output, None).await?;
+        // stay on the adapter the user picked if it's still there
+        let adapter = session.adapter(self.adapter.name()).unwrap_or(default_adapter);
+
+        let (agent_tx, agent_rx) = mpsc::unbounded_channel();
+        self.agent_handle = session.register_agent(create_agent(agent_tx)).await?;
+        self.agent_rx = agent_rx;
+        self.session_events = session.events().await?.boxed();
+        self.session = session;
+        self.adapter = adapter;
+
+        self.resync().await?;
+
+        if was_discovering && self.adapter.is_powered().await? {
+            self.discovery_events = Some(self.adapter.discover_devices().await?.boxed());
+        }
+
+        Ok(())
+    }
+
+    /// Handles a request, reconnecting and repeating it once if the bluez connection dropped underneath it
+    async fn handle_request_reconnecting(&mut self, request: WorkerRequest) -> anyhow::Result<()> {
+        let res = handle_reconnecting(
+            &TokioClock,
+            self,
+            request.clone(),
+            request.is_retryable(),
+            async |worker: &mut Self, request| worker.handle_request(request).await,
+            async |worker: &mut Self| worker.reconnect().await,
+        )
+        .await
+        .context(format!("Could not handle request: {request:?}"));
+
+        if res.is_err() {
+            match request {
+                WorkerRequest::SetEnabled(_) => _ = self.output.send(WorkerEvent::PowerFailed).await,
+                WorkerRequest::RefreshDevices => _ = self.output.send(WorkerEvent::RefreshFailed).await,
+                _ => {}
+            }
+        }
+
+        self.warn_on_error(res).await
+    }
+
+    /// Tells the user about a failure instead of restarting the worker over it. Only a lost bluez connection is
+    /// still passed on, the worker can't carry on without one
+    async fn warn_on_error(&mut self, res: anyhow::Result<()>) -> anyhow::Result<()> {
+        match res {
+            Err(e) if !is_disconnected(&e) => {
+                tracing::warn!("{e:?}");
+                _ = self.output.send(WorkerEvent::Warning(format!("{e:#}"))).await;
+                Ok(())
+            }
+            res => res,
+        }
+    }
+
+    /// Re-subscribes to adapter events and rebuilds the device map from scratch, used when a stream we depend
+    /// on ends (e.g. bluetoothd restarted) and events may have been missed
+    async fn resync(&mut self) -> anyhow::Result<()> {
+        self.adapter_events = self.adapter.events().await?.boxed();
+        // the adapter may have changed, or come back as a new radio
+        self.rfkill_idx = None;
+        // the new device map already has the latest state
+        self.pending_updates.clear();
+
+        let (mut bt_device_map, mut device_handles) =
+            create_device_maps(&self.adapter, &self.device_tx, &mut self.output).await?;
+
+        self.foreign_devices = if self.config.show_all_adapters {
+            add_foreign_devices(
+                &self.session,
+                &self.adapter,
+                &self.device_tx,
+                &mut bt_device_map,
+                &mut device_handles,
+            )
+            .await?
+        } else {
+            HashMap::new()
+        };
+
+        std::mem::replace(&mut self.device_handles, device_handles)
+            .into_values()
+            .for_each(|h| h.abort());
+
+        let enabled = self.adapter.is_powered().await?;
+        let adapter_info = AdapterInfo::read(&self.adapter).await?;
+        let adapters = self.session.adapter_names().await?;
+        _ = self.output.send(WorkerEvent::Enabled(enabled)).await;
+        _ = self.output.send(WorkerEvent::AdapterInfo(adapter_info)).await;
+        _ = self.output.send(discoverable_event(&self.adapter).await?).await;
+        _ = self.output.send(WorkerEvent::Pairable(self.adapter.is_pairable().await?)).await;
+        _ = self.output.send(WorkerEvent::Discovering(self.adapter.is_discovering().await?)).await;
+        _ = self.output.send(WorkerEvent::Adapters(adapters)).await;
+        _ = self.output.send(WorkerEvent::DeviceMap(bt_device_map)).await;
+
+        Ok(())
+    }
+
+    async fn handle_session_event(&mut self, event: SessionEvent) -> anyhow::Result<()> {
+        match event {
+            // the adapter the user picked is back
+            SessionEvent::AdapterAdded(name)
+                if self.config.selected_adapter.as_deref() == Some(name.as_str()) =>
+            {
+                tracing::info!("adapter {name} added");
+                return self.handle_request(WorkerRequest::SelectAdapter(name)).await;
+            }
+            SessionEvent::AdapterAdded(name) => tracing::info!("adapter {name} added"),
+            SessionEvent::AdapterRemoved(name) if name == self.adapter.name() => {
+                return self.handle_adapter_removed().await;
+            }
+            SessionEvent::AdapterRemoved(name) => tracing::info!("adapter {name} removed"),
+        }
+
+        if self.config.show_all_adapters {
+            // devices of the added or removed adapter come or go with it
+            self.resync().await?;
+        } else {
+            let adapters = self.session.adapter_names().await?;
+            _ = self.output.send(WorkerEvent::Adapters(adapters)).await;
+        }
+        Ok(())
+    }
+
+    /// Moves on to another adapter when the one in use is unplugged, or stops the worker when there is none left
+    async fn handle_adapter_removed(&mut self) -> anyhow::Result<()> {
+        tracing::info!("adapter {} removed", self.adapter.name());
+        self.discovery_events = None;
+        self.pending_updates.clear();
+        self.device_handles.drain().for_each(|(_, h)| h.abort());
+
+        match self.session.default_adapter().await {
+            Ok(adapter) => {
+                tracing::info!("switching to adapter {}", adapter.name());
+                self.adapter = adapter;
+                self.resync().await
+            }
+            Err(e) if e.kind == bluer::ErrorKind::NotFound => {
+                _ = self.output.send(WorkerEvent::NoAdapter).await;
+                self.adapter_removed = true;
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
         }
     }
 
     async fn handle_adapter_event(&mut self, event: AdapterEvent) -> anyhow::Result<()> {
         let message = match event {
             AdapterEvent::PropertyChanged(AdapterProperty::Powered(v)) => WorkerEvent::Enabled(v),
+            AdapterEvent::PropertyChanged(AdapterProperty::Pairable(v)) => WorkerEvent::Pairable(v),
+            AdapterEvent::PropertyChanged(AdapterProperty::Alias(_)) => {
+                WorkerEvent::AdapterInfo(AdapterInfo::read(&self.adapter).await?)
+            }
+            AdapterEvent::PropertyChanged(AdapterProperty::Discoverable(_)) => {
+                discoverable_event(&self.adapter).await?
+            }
+            AdapterEvent::PropertyChanged(AdapterProperty::Discovering(discovering)) => {
+                // bluez may take a while to start scanning, the popup's scan gets its full time from when it does
+                if discovering
+                    && self.popup_discovery
+                    && self.discovery_events.is_some()
+                    && let Some(timeout) = self.config.discovery_timeout()
+                {
+                    self.discovery_timeout.as_mut().reset(tokio::time::Instant::now() + timeout);
+                }
+                WorkerEvent::Discovering(discovering)
+            }
             AdapterEvent::DeviceRemoved(addr) => {
                 // DeviceAdded and DeviceRemoved fire both when a device connects/disconnects, and when a device is 
                 // added/removed from the adapter database, this is the only way to distinguish between them 🙄
@@ -122,6 +713,7 @@ impl BluetoothWorker {
 
                 if let Some(handle) = self.device_handles.remove(&addr) {
                     handle.abort();
+                    self.pending_updates.remove(&addr);
                     WorkerEvent::DeviceRemoved(addr)
                 } else {
                     return Ok(())
@@ -130,7 +722,8 @@ impl BluetoothWorker {
             AdapterEvent::DeviceAdded(addr) => {
                 let device = self.adapter.device(addr)?;
 
-                if device.name().await?.is_none() || self.device_handles.contains_key(&addr) {
+                // nameless devices are still sent, the app decides whether to show them
+                if self.device_handles.contains_key(&addr) {
                     return Ok(());
                 }
 
@@ -153,13 +746,219 @@ impl BluetoothWorker {
         Ok(())
     }
 
+    /// The adapter a device belongs to, so actions on devices from other adapters go to the right place
+    fn device_adapter(&self, addr: bluer::Address) -> bluer::Result<bluer::Adapter> {
+        match self.foreign_devices.get(&addr) {
+            Some(name) => self.session.adapter(name),
+            None => Ok(self.adapter.clone()),
+        }
+    }
+
+    fn device(&self, addr: bluer::Address) -> bluer::Result<bluer::Device> {
+        self.device_adapter(addr)?.device(addr)
+    }
+
+    async fn connected_devices(&self) -> HashSet<bluer::Address> {
+        let mut connected = HashSet::new();
+        for addr in self.device_handles.keys() {
+            if let Ok(device) = self.device(*addr)
+                && device.is_connected().await.unwrap_or_default()
+            {
+                connected.insert(*addr);
+            }
+        }
+        connected
+    }
+
+    /// Connects the trusted devices that were connected when bluetooth was turned off
+    fn auto_reconnect_trusted(&mut self) {
+        let addrs = std::mem::take(&mut self.connected_before_disable);
+        if !self.config.auto_reconnect_trusted {
+            return;
+        }
+
+        for addr in addrs {
+            let Ok(device) = self.device(addr) else {
+                continue;
+            };
+
+            // already being connected, e.g. the user clicked it right after turning bluetooth on
+            if !self.connecting.lock().unwrap().insert(addr) {
+                continue;
+            }
+
+            let connecting = self.connecting.clone();
+            let connect_limit = self.connect_limit.clone();
+            let timeout = self.config.connect_timeout();
+            let retry = self.config.retry_policy();
+            tokio::spawn(async move {
+                let trusted = device.is_paired().await.unwrap_or_default()
+                    && device.is_trusted().await.unwrap_or_default();
+                if trusted && let Err(e) = connect_limited(&connect_limit, timeout, retry, &device).await {
+                    tracing::warn!("could not reconnect trusted device {addr}: {e}");
+                }
+                connecting.lock().unwrap().remove(&addr);
+            });
+        }
+    }
+
+    /// Samples the signal strength of connected devices, bluez doesn't push rssi changes outside of discovery.
+    /// The reads run in the background so a slow device doesn't hold up the worker.
+    fn poll_link_quality(&self) {
+        for addr in self.device_handles.keys() {
+            let Ok(device) = self.device(*addr) else {
+                continue;
+            };
+            let device_tx = self.device_tx.clone();
+
+            tokio::spawn(async move {
+                if !device.is_connected().await.unwrap_or_default() {
+                    return;
+                }
+
+                if let Ok(Some(rssi)) = device.rssi().await {
+                    _ = device_tx.send((device.address(), DeviceUpdate::Rssi(rssi)));
+                }
+            });
+        }
+    }
+
+    /// The rfkill index of the adapter's radio, only searched for once per adapter
+    fn rfkill_idx(&mut self) -> anyhow::Result<u32> {
+        if let Some(idx) = self.rfkill_idx {
+            return Ok(idx);
+        }
+
+        let idx = find_adapter_idx(self.adapter.name())?;
+        self.rfkill_idx = Some(idx);
+        Ok(idx)
+    }
+
+    /// Points the confirmation timer at the earliest pending deadline
+    fn schedule_confirmation_timeout(&mut self) {
+        if let Some(deadline) = self.confirmation_senders.values().map(|(_, d)| *d).min() {
+            self.confirmation_timeout.as_mut().reset(deadline);
+        }
+    }
+
+    /// Rejects the pairings the user didn't answer in time
+    async fn expire_confirmations(&mut self) -> anyhow::Result<()> {
+        // an answer sent before the deadline may still be queued, it wins over the timeout
+        while let Ok(r) = self.requests.try_recv() {
+            self.handle_request_reconnecting(r.clone())
+                .await
+                .context(format!("Could not handle request: {:?}", r))?;
+        }
+
+        let now = tokio::time::Instant::now();
+        let expired: Vec<_> = self
+            .confirmation_senders
+            .iter()
+            .filter(|(_, (_, deadline))| *deadline <= now)
+            .map(|(addr, _)| *addr)
+            .collect();
+
+        for addr in expired {
+            if let Some((sender, _)) = self.confirmation_senders.remove(&addr) {
+                tracing::info!("pairing confirmation for {addr} timed out");
+                _ = sender.send(false);
+                _ = self.output.send(WorkerEvent::ConfirmationExpired(addr)).await;
+            }
+        }
+
+        self.schedule_confirmation_timeout();
+        Ok(())
+    }
+
+    /// Reads the battery of connected devices, for devices that only report it when asked.
+    /// The levels come back through the device channel, unchanged ones are dropped there.
+    fn poll_battery(&self) {
+        for addr in self.device_handles.keys() {
+            let Ok(device) = self.device(*addr) else {
+                continue;
+            };
+            let device_tx = self.device_tx.clone();
+
+            tokio::spawn(async move {
+                if !device.is_connected().await.unwrap_or_default() {
+                    return;
+                }
+
+                if let Ok(Some(battery)) = device.battery_percentage().await {
+                    _ = device_tx.send((device.address(), DeviceUpdate::Battery(battery)));
+                }
+
+                let batteries = read_batteries(&device).await;
+                if !batteries.is_empty() {
+                    _ = device_tx.send((device.address(), DeviceUpdate::Batteries(batteries)));
+                }
+            });
+        }
+    }
+
+    /// Starts or stops the background scan, and schedules the next change
+    async fn advance_scan_cycle(&mut self) -> anyhow::Result<()> {
+        let next = if self.discovery_events.take().is_some() {
+            tracing::info!("background scan finished");
+            self.config.scan_interval()
+        } else {
+            if self.adapter.is_powered().await? {
+                self.discovery_events = Some(self.adapter.discover_devices().await?.boxed());
+                tracing::info!("background scan started");
+            }
+            self.config.scan_duration()
+        };
+
+        self.scan_cycle.as_mut().reset(tokio::time::Instant::now() + next);
+        Ok(())
+    }
+
     async fn handle_agent_event(&mut self, event: AgentEvent) -> anyhow::Result<()> {
         match event {
             AgentEvent::RequestConfirmation(passkey, addr, output) => {
+                // the user already vouched for a trusted device, it's likely just pairing again after a reset
+                if self.config.auto_confirm_trusted {
+                    let trusted = match self.device(addr) {
+                        Ok(device) => device.is_trusted().await.unwrap_or_default(),
+                        Err(_) => false,
+                    };
+                    if trusted {
+                        tracing::info!("confirmed pairing of trusted device {addr}");
+                        _ = output.send(true);
+                        return Ok(());
+                    }
+                }
+
                 tracing::info!("worker received confirmation request...");
-                self.confirmation_senders.insert(addr.clone(), output);
+                let deadline = tokio::time::Instant::now() + CONFIRMATION_TIMEOUT;
+                self.confirmation_senders.insert(addr.clone(), (output, deadline));
+                self.schedule_confirmation_timeout();
                 _ = self.output.send(WorkerEvent::ConfirmCode(passkey.to_string(), addr)).await;
             }
+            AgentEvent::RequestPinCode(addr, output) => {
+                tracing::info!("worker received pin code request...");
+                self.pin_code_senders.insert(addr, output);
+                _ = self.output.send(WorkerEvent::RequestPinCode(addr)).await;
+            }
+            AgentEvent::RequestPasskey(addr, output) => {
+                tracing::info!("worker received passkey request...");
+                // same prompt as a PIN, the answer is parsed when it comes back
+                self.passkey_senders.insert(addr, output);
+                _ = self.output.send(WorkerEvent::RequestPinCode(addr)).await;
+            }
+            AgentEvent::DisplayPasskey(addr, code, entered) => {
+                _ = self.output.send(WorkerEvent::DisplayPasskey(addr, code, entered)).await;
+            }
+            AgentEvent::AuthorizeService(addr, service, output) => {
+                if service.is_some() && self.config.always_allow_services(addr) {
+                    _ = output.send(true);
+                    return Ok(());
+                }
+
+                tracing::info!("worker received authorization request...");
+                self.authorization_senders.insert(addr, output);
+                _ = self.output.send(WorkerEvent::AuthorizeService(addr, service)).await;
+            }
         }
 
         Ok(())
@@ -168,8 +967,16 @@ impl BluetoothWorker {
     async fn handle_request(&mut self, request: WorkerRequest) -> anyhow::Result<()> {
         match request {
             WorkerRequest::SetDiscovery(v) => {
+                self.popup_discovery = v;
+                // closing the popup ends its scan, the background cycle picks up after a full interval
+                let next_scan = tokio::time::Instant::now() + self.config.scan_interval();
+                self.scan_cycle.as_mut().reset(next_scan);
+
                 if v && self.adapter.is_powered().await? {
                     self.discovery_events = Some(self.adapter.discover_devices().await?.boxed());
+                    if let Some(timeout) = self.config.discovery_timeout() {
+                        self.discovery_timeout.as_mut().reset(tokio::time::Instant::now() + timeout);
+                    }
                     tracing::info!("started device discovery")
                 } else {
                     self.discovery_events = None;
@@ -177,113 +984,593 @@ impl BluetoothWorker {
                 }
             }
             WorkerRequest::ConnectDevice(addr) => {
-                let device = self.adapter.device(addr)?;
+                let device = self.device(addr)?;
                 let mut output = self.output.clone();
+                let connect_limit = self.connect_limit.clone();
+                let timeout = self.config.connect_timeout();
+                let retry = self.config.retry_policy();
+                let device_tx = self.device_tx.clone();
+                let connecting = self.connecting.clone();
+                if !connecting.lock().unwrap().insert(addr) {
+                    tracing::info!("{addr} is already connecting");
+                    return Ok(());
+                }
                 tokio::spawn(async move {
-                    if let Err(e) = connect_with_retry(&device).await {
+                    let res = connect_limited(&connect_limit, timeout, retry, &device).await;
+                    connecting.lock().unwrap().remove(&addr);
+
+                    if let Err(e) = res {
                         tracing::error!("device failed to connect: {e}");
-                        _ = output.send(WorkerEvent::ConnectFailed(device.address())).await
+                        let err = ConnectError::from_error(&e);
+                        _ = output.send(WorkerEvent::ConnectFailed(device.address(), Some(err))).await;
+                        return;
+                    }
+
+                    // connecting an unpaired device pairs it
+                    report_paired(device.address(), device.is_paired(), &device_tx).await;
+                });
+            }
+            WorkerRequest::PairDevice(addr) => {
+                let device = self.device(addr)?;
+                let mut output = self.output.clone();
+                let device_tx = self.device_tx.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = device.pair().await {
+                        tracing::error!("device failed to pair: {e}");
+                        let err = ConnectError::from_error(&e);
+                        _ = output.send(WorkerEvent::ConnectFailed(addr, Some(err))).await;
+                        return;
                     }
+
+                    // same as after connecting, don't rely on bluez emitting the Paired change
+                    _ = device_tx.send((addr, DeviceUpdate::Paired(true)));
                 });
             }
             WorkerRequest::DisconnectDevice(addr) => {
-                let device = self.adapter.device(addr)?;
+                let device = self.device(addr)?;
+                let mut output = self.output.clone();
                 tokio::spawn(async move {
                     if let Err(e) = device.disconnect().await {
                         tracing::warn!("device failed to disconnect: {e}");
+                        _ = output.send(WorkerEvent::Warning(format!("Could not disconnect {addr}: {e}"))).await;
                     }
                 });
             }
+            WorkerRequest::DisconnectAll => {
+                for addr in self.connected_devices().await {
+                    let device = self.device(addr)?;
+                    let mut output = self.output.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = device.disconnect().await {
+                            tracing::warn!("device failed to disconnect: {e}");
+                            _ = output.send(WorkerEvent::Warning(format!("Could not disconnect {addr}: {e}"))).await;
+                        }
+                    });
+                }
+            }
+            WorkerRequest::SetDiscoverable(discoverable) => {
+                if discoverable {
+                    self.adapter.set_discoverable_timeout(self.config.discoverable_timeout_secs()).await?;
+                }
+                self.adapter.set_discoverable(discoverable).await?;
+            }
+            WorkerRequest::SetPairable(pairable) => {
+                self.adapter.set_pairable(pairable).await?;
+            }
             WorkerRequest::CancelConnect(addr) => {
-                let device = self.adapter.device(addr)?;
+                let device = self.device(addr)?;
+                let mut output = self.output.clone();
+                self.reconnecting.lock().unwrap().remove(&addr);
+                tokio::spawn(async move {
+                    if let Err(e) = device.disconnect().await {
+                        tracing::warn!("device failed to disconnect: {e}");
+                    }
+                    _ = output.send(WorkerEvent::ConnectFailed(device.address(), None)).await
+                });
+            }
+            WorkerRequest::ReconnectDevice(addr) => {
+                let device = self.device(addr)?;
                 let mut output = self.output.clone();
+                let reconnecting = self.reconnecting.clone();
+                let connect_limit = self.connect_limit.clone();
+                let timeout = self.config.connect_timeout();
+                let retry = self.config.retry_policy();
+                reconnecting.lock().unwrap().insert(addr);
                 tokio::spawn(async move {
                     if let Err(e) = device.disconnect().await {
                         tracing::warn!("device failed to disconnect: {e}");
                     }
-                    _ = output.send(WorkerEvent::ConnectFailed(device.address())).await
+
+                    let res = connect_limited(&connect_limit, timeout, retry, &device).await;
+                    // cancelled mid-cycle, CancelConnect already reported the failure
+                    if !reconnecting.lock().unwrap().remove(&addr) {
+                        return;
+                    }
+
+                    if let Err(e) = res {
+                        tracing::error!("device failed to reconnect: {e}");
+                        let err = ConnectError::from_error(&e);
+                        _ = output.send(WorkerEvent::ConnectFailed(addr, Some(err))).await
+                    }
                 });
             }
             WorkerRequest::SetEnabled(enabled) => {
                 tracing::info!("Setting bluetooth enabled to {}", enabled);
 
-                if self.adapter.set_powered(enabled).await.is_ok() {
+                let soft_block = !enabled && self.config.disable_mode == DisableMode::SoftBlock;
+
+                if !enabled {
+                    self.connected_before_disable = self.connected_devices().await;
+                }
+
+                if !soft_block && self.adapter.set_powered(enabled).await.is_ok() {
+                    if enabled {
+                        self.auto_reconnect_trusted();
+                    }
                     return Ok(())
                 }
 
-                let idx = find_adapter_idx(self.adapter.name())?;
+                let idx = self.rfkill_idx()?;
 
                 rfkill_set_enabled(idx, enabled)?;
 
                 if enabled {
                     let (bt_device_map, device_handles) =
-                        create_device_maps(&self.adapter, &self.device_tx).await?;
+                        create_device_maps(&self.adapter, &self.device_tx, &mut self.output).await?;
 
-                    _ = std::mem::replace(&mut self.device_handles, device_handles);
+                    std::mem::replace(&mut self.device_handles, device_handles)
+                        .into_values()
+                        .for_each(|h| h.abort());
 
                     _ = self
                         .output
                         .send(WorkerEvent::DeviceMap(bt_device_map))
                         .await;
+
+                    self.auto_reconnect_trusted();
                 } else {
                     self.device_handles.drain().for_each(|(_, h)| h.abort());
                 }
             },
+            WorkerRequest::SetAirplane(airplane) => {
+                tracing::info!("Setting airplane mode to {}", airplane);
+                // bluez powers the adapter down when the radio is blocked, and back up when it's unblocked. The
+                // new state is reported back through the rfkill events
+                let idx = self.rfkill_idx()?;
+                rfkill_set_enabled(idx, !airplane)?;
+            }
             WorkerRequest::ConfirmCode(addr, confirm) => {
-                if let Some(sender) = self.confirmation_senders.remove(&addr) {
-                    _ = sender.send(confirm)
+                if let Some((sender, _)) = self.confirmation_senders.remove(&addr) {
+                    _ = sender.send(confirm);
+                    self.schedule_confirmation_timeout();
+                }
+                if !confirm {
+                    // dropping the sender rejects the request
+                    self.pin_code_senders.remove(&addr);
+                    self.passkey_senders.remove(&addr);
                 }
             }
-        }
-        Ok(())
-    }
+            WorkerRequest::AuthorizeService(addr, allow) => {
+                if let Some(sender) = self.authorization_senders.remove(&addr) {
+                    _ = sender.send(allow)
+                }
+            }
+            WorkerRequest::SubmitPinCode(addr, pin) => {
+                if let Some(sender) = self.pin_code_senders.remove(&addr) {
+                    _ = sender.send(pin)
+                } else if let Some(sender) = self.passkey_senders.remove(&addr) {
+                    // a passkey that isn't a number is rejected by dropping the sender
+                    if let Ok(passkey) = pin.trim().parse() {
+                        _ = sender.send(passkey)
+                    }
+                }
+            }
+            WorkerRequest::RefreshDevices => {
+                tracing::info!("refreshing device list");
+                self.resync().await?;
+            }
+            WorkerRequest::FetchDevice(addr) => {
+                let adapter = self.device_adapter(addr)?;
+                if !adapter.device_addresses().await?.contains(&addr) {
+                    tracing::info!("{addr} to fetch is gone");
+                    _ = self.output.send(WorkerEvent::DeviceRemoved(addr)).await;
+                    return Ok(());
+                }
 
-    async fn listen(&mut self) -> anyhow::Result<()> {
-        tokio::select! {
-            Some(r) = self.requests.recv() => self.handle_request(r.clone()).await
-                .context(format!("Could not handle request: {:?}", r)),
-            Some(e) = self.adapter_events.next() => self.handle_adapter_event(e.clone()).await
-                .context(format!("Could not handle adapter event: {:?}", e)),
-            Some(e) = async {
-                match self.discovery_events.as_mut() {
-                    Some(stream) => stream.next().await,
-                    None => futures::future::pending().await, // Never resolves
-                } 
-            } => {
-                self.handle_adapter_event(e.clone()).await
-                    .context(format!("Could not handle discovery event: {:?}", e))
-            },
-            Some((a, u)) = self.device_rx.recv() => {
-                _ = self.output.send(WorkerEvent::DeviceUpdate(a, u)).await;
-                Ok(())
-            },
-            Some(e) = self.agent_rx.recv() => self.handle_agent_event(e).await,
-        }
-    }
-}
+                let device = adapter.device(addr)?;
+                if !self.device_handles.contains_key(&addr) {
+                    let events = device.events().await?;
+                    let device_tx = self.device_tx.clone();
+                    self.device_handles.insert(addr, tokio::spawn(device_listener(addr, events, device_tx)));
+                }
 
-async fn get_connection() -> anyhow::Result<(bluer::Adapter, bluer::Session)> {
+                let mut bt_device = BluetoothDevice::from_device(&device).await;
+                bt_device.adapter = self.foreign_devices.get(&addr).cloned();
+                _ = self.output.send(WorkerEvent::DeviceAdded(bt_device)).await;
+            }
+            WorkerRequest::SetConfig(config) => {
+                let resync = config.show_all_adapters != self.config.show_all_adapters;
+                if config.background_scan != self.config.background_scan && !self.popup_discovery {
+                    // start the new cycle right away, or stop a background scan that's running
+                    self.discovery_events = None;
+                    self.scan_cycle.as_mut().reset(tokio::time::Instant::now());
+                }
+                if config.battery_poll_secs != self.config.battery_poll_secs {
+                    self.battery_poll = battery_poll(&config);
+                }
+                if config.concurrent_connects() != self.config.concurrent_connects() {
+                    // connects already holding a permit finish against the old limit
+                    self.connect_limit = Arc::new(Semaphore::new(config.concurrent_connects()));
+                }
+                self.config = config;
+
+                if resync {
+                    self.resync().await?;
+                }
+            }
+            WorkerRequest::RemoveDevice(addr) => {
+                if let Err(e) = self.device_adapter(addr)?.remove_device(addr).await {
+                    tracing::warn!("could not remove device {addr}: {e}");
+                    return Ok(());
+                }
+
+                // report the removal here, with the handle gone the adapter's DeviceRemoved event is ignored
+                if let Some(handle) = self.device_handles.remove(&addr) {
+                    handle.abort();
+                }
+                self.foreign_devices.remove(&addr);
+                self.pending_updates.remove(&addr);
+                _ = self.output.send(WorkerEvent::DeviceRemoved(addr)).await;
+            }
+            WorkerRequest::SetHeadsetProfile(addr, enabled) => {
+                let device = self.device(addr)?;
+                if device.is_connected().await? {
+                    tokio::spawn(set_headset_profile(device, enabled));
+                }
+            }
+            WorkerRequest::SetAlias(addr, alias) => {
+                // the new name is reported back through the device listener
+                self.device(addr)?.set_alias(alias).await?;
+            }
+            WorkerRequest::SetTrusted(addr, trusted) => {
+                // the change is reported back through the device listener
+                self.device(addr)?.set_trusted(trusted).await?;
+            }
+            WorkerRequest::SetBlocked(addr, blocked) => {
+                // the change is reported back through the device listener
+                self.device(addr)?.set_blocked(blocked).await?;
+            }
+            WorkerRequest::SelectAdapter(name) => {
+                if name == self.adapter.name() {
+                    return Ok(());
+                }
+
+                tracing::info!("switching to adapter {name}");
+                let was_discovering = self.discovery_events.take().is_some();
+                self.adapter = self.session.adapter(&name)?;
+
+                // resync drops the old adapter's device listeners and event stream
+                self.resync().await?;
+
+                if was_discovering && self.adapter.is_powered().await? {
+                    self.discovery_events = Some(self.adapter.discover_devices().await?.boxed());
+                }
+            }
+            WorkerRequest::ConnectProfile(addr, uuid) => {
+                let device = self.device(addr)?;
+                let mut output = self.output.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = device.connect_profile(&uuid).await {
+                        tracing::warn!("could not connect profile {uuid} of {addr}: {e}");
+                        let profile = service_name(uuid);
+                        _ = output.send(WorkerEvent::Warning(format!("Could not connect {profile} of {addr}: {e}"))).await;
+                    }
+                });
+            }
+            WorkerRequest::FetchDetails(addr) => {
+                let info = DeviceInfo::read(&self.device(addr)?).await?;
+                _ = self.output.send(WorkerEvent::DeviceDetails(addr, info)).await;
+            }
+            WorkerRequest::SetAdapterAlias(alias) => {
+                // the new name is reported back through the adapter events
+                self.adapter.set_alias(alias).await?;
+            }
+            WorkerRequest::SetWakeAllowed(addr, allowed) => {
+                // the property change is reported back through the device listener
+                self.device(addr)?.set_wake_allowed(allowed).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn listen(&mut self) -> anyhow::Result<()> {
+        tokio::select! {
+            r = self.requests.recv() => match r {
+                Some(r) => self.handle_request_reconnecting(r).await,
+                None => bail!("Request channel closed"),
+            },
+            e = self.adapter_events.next() => match e {
+                Some(e) => {
+                    let res = self.handle_adapter_event(e.clone()).await;
+                    self.warn_on_error(res.context(format!("Could not handle adapter event: {e:?}"))).await
+                }
+                None if !self.session.adapter_names().await?.iter().any(|name| name == self.adapter.name()) => {
+                    self.handle_adapter_removed().await
+                }
+                None => {
+                    tracing::warn!("adapter event stream ended, resyncing");
+                    self.resync().await.context("Could not resync after adapter event stream ended")
+                }
+            },
+            e = async {
+                match self.discovery_events.as_mut() {
+                    Some(stream) => stream.next().await,
+                    None => futures::future::pending().await, // Never resolves
+                } 
+            } => match e {
+                Some(e) => {
+                    let res = self.handle_adapter_event(e.clone()).await;
+                    self.warn_on_error(res.context(format!("Could not handle discovery event: {e:?}"))).await
+                }
+                None => {
+                    tracing::warn!("discovery event stream ended");
+                    self.discovery_events = None;
+                    Ok(())
+                }
+            },
+            // never ends while the session is alive
+            Some(e) = self.session_events.next() => {
+                self.handle_session_event(e).await.context("Could not handle adapter change")
+            },
+            // never ends, the worker holds a sender
+            Some((a, u)) = self.device_rx.recv() => {
+                if matches!(u, DeviceUpdate::Connected(false))
+                    && self.reconnecting.lock().unwrap().contains(&a)
+                {
+                    return Ok(());
+                }
+
+                // polled levels repeat until the battery actually drains
+                if let DeviceUpdate::Battery(battery) = u
+                    && self.battery_levels.insert(a, battery) == Some(battery)
+                {
+                    return Ok(());
+                }
+
+                // applies to every connect, whether the applet started it or the device reconnected by itself
+                if matches!(u, DeviceUpdate::Connected(true))
+                    && !self.config.headset_profile_enabled(a)
+                    && let Ok(device) = self.device(a)
+                {
+                    tokio::spawn(set_headset_profile(device, false));
+                }
+
+                if matches!(u, DeviceUpdate::Connected(true))
+                    && let Ok(device) = self.device(a)
+                {
+                    let device_tx = self.device_tx.clone();
+                    tokio::spawn(async move {
+                        // gatt services are only there once bluez has resolved them after connecting
+                        for _ in 0..10 {
+                            if device.is_services_resolved().await.unwrap_or_default() {
+                                break;
+                            }
+                            tokio::time::sleep(Duration::from_secs(1)).await;
+                        }
+
+                        let batteries = read_batteries(&device).await;
+                        if !batteries.is_empty() {
+                            _ = device_tx.send((device.address(), DeviceUpdate::Batteries(batteries)));
+                        }
+                    });
+                }
+
+                // connection changes go out right away, signal and name changes pile up while scanning
+                if matches!(
+                    u,
+                    DeviceUpdate::Rssi(_) | DeviceUpdate::Name(_) | DeviceUpdate::DeviceName(_) | DeviceUpdate::DeviceType(_)
+                ) {
+                    if self.pending_updates.is_empty() {
+                        self.flush_updates.as_mut().reset(tokio::time::Instant::now() + UPDATE_DEBOUNCE);
+                    }
+                    let pending = self.pending_updates.entry(a).or_default();
+                    pending.retain(|p| std::mem::discriminant(p) != std::mem::discriminant(&u));
+                    pending.push(u);
+                    return Ok(());
+                }
+
+                _ = self.output.send(WorkerEvent::DeviceUpdate(a, u)).await;
+                Ok(())
+            },
+            _ = &mut self.flush_updates, if !self.pending_updates.is_empty() => {
+                for (a, updates) in std::mem::take(&mut self.pending_updates) {
+                    for u in updates {
+                        _ = self.output.send(WorkerEvent::DeviceUpdate(a, u)).await;
+                    }
+                }
+                Ok(())
+            },
+            _ = &mut self.confirmation_timeout, if !self.confirmation_senders.is_empty() => {
+                self.expire_confirmations().await
+            },
+            _ = &mut self.discovery_timeout, if self.popup_discovery
+                && self.discovery_events.is_some()
+                && self.config.discovery_timeout().is_some() =>
+            {
+                // popup_discovery stays set, so the background scan doesn't start up again while the popup is open
+                self.discovery_events = None;
+                tracing::info!("stopped device discovery after timeout");
+                _ = self.output.send(WorkerEvent::DiscoveryTimedOut).await;
+                Ok(())
+            },
+            _ = &mut self.scan_cycle, if self.config.background_scan && !self.popup_discovery => {
+                self.advance_scan_cycle().await.context("Could not advance background scan")
+            },
+            Some((idx, soft, hard)) = self.rfkill_events.next() => {
+                if self.rfkill_idx().ok() == Some(idx) {
+                    _ = self.output.send(WorkerEvent::SoftBlocked(soft)).await;
+                    _ = self.output.send(WorkerEvent::HardBlocked(hard)).await;
+                }
+                Ok(())
+            },
+            _ = self.link_quality_poll.tick() => {
+                self.poll_link_quality();
+                Ok(())
+            },
+            _ = async {
+                match self.battery_poll.as_mut() {
+                    Some(interval) => _ = interval.tick().await,
+                    None => futures::future::pending().await,
+                }
+            } => {
+                self.poll_battery();
+                Ok(())
+            },
+            e = self.agent_rx.recv() => match e {
+                Some(e) => self.handle_agent_event(e).await,
+                None => {
+                    tracing::warn!("agent channel closed, registering a new agent");
+                    let (agent_tx, agent_rx) = mpsc::unbounded_channel();
+                    self.agent_handle = self.session.register_agent(create_agent(agent_tx)).await?;
+                    self.agent_rx = agent_rx;
+                    Ok(())
+                }
+            },
+        }
+    }
+}
+
+/// Reads every battery of a device that has several, e.g. earbuds reporting left, right and the case as separate
+/// battery services. bluez itself only reports the first one, so an empty list means there's nothing to add to it
+async fn read_batteries(device: &bluer::Device) -> Vec<(String, u8)> {
+    let Ok(services) = device.services().await else {
+        return Vec::new();
+    };
+
+    let mut batteries = Vec::new();
+    for service in services {
+        if service.uuid().await.ok() != Some(BATTERY_SERVICE) {
+            continue;
+        }
+
+        let Ok(characteristics) = service.characteristics().await else {
+            continue;
+        };
+        for characteristic in characteristics {
+            if characteristic.uuid().await.ok() != Some(BATTERY_LEVEL) {
+                continue;
+            }
+
+            let Some(&level) = characteristic.read().await.ok().as_ref().and_then(|v| v.first()) else {
+                continue;
+            };
+
+            let mut label = String::new();
+            for descriptor in characteristic.descriptors().await.unwrap_or_default() {
+                if descriptor.uuid().await.ok() == Some(USER_DESCRIPTION)
+                    && let Ok(value) = descriptor.read().await
+                {
+                    label = String::from_utf8_lossy(&value).trim().to_string();
+                }
+            }
+
+            batteries.push((label, level.min(100)));
+        }
+    }
+
+    if batteries.len() > 1 {
+        batteries
+    } else {
+        Vec::new()
+    }
+}
+
+async fn discoverable_event(adapter: &bluer::Adapter) -> anyhow::Result<WorkerEvent> {
+    let discoverable = adapter.is_discoverable().await?;
+    let timeout = adapter.discoverable_timeout().await?;
+
+    // a timeout of 0 keeps the adapter discoverable until turned off
+    Ok(WorkerEvent::Discoverable(
+        discoverable,
+        (timeout > 0).then(|| Duration::from_secs(timeout.into())),
+    ))
+}
+
+fn battery_poll(config: &BluetoothAppletConfig) -> Option<tokio::time::Interval> {
+    config.battery_poll_interval().map(|period| {
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        interval
+    })
+}
+
+/// Connects to bluez and picks the `preferred` adapter, or the default one when it isn't plugged in
+async fn get_connection(
+    output: &mut futures::channel::mpsc::Sender<WorkerEvent>,
+    preferred: Option<&str>,
+) -> anyhow::Result<(bluer::Adapter, bluer::Session)> {
     let session = bluer::Session::new().await?;
-    let adapter = session.default_adapter().await?;
+    // listen before looking, so an adapter added in between isn't missed
+    let mut events = session.events().await?;
 
-    Ok((adapter, session))
+    loop {
+        match session.default_adapter().await {
+            Ok(adapter) => {
+                let adapter = match preferred {
+                    Some(name) if session.adapter_names().await?.iter().any(|n| n == name) => {
+                        session.adapter(name)?
+                    }
+                    Some(name) => {
+                        tracing::info!("adapter {name} is gone, using {}", adapter.name());
+                        adapter
+                    }
+                    None => adapter,
+                };
+                return Ok((adapter, session));
+            }
+            Err(e) if e.kind == bluer::ErrorKind::NotFound => {
+                tracing::info!("no bluetooth adapter, waiting for one to be added");
+                _ = output.send(WorkerEvent::NoAdapter).await;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        loop {
+            match events.next().await {
+                Some(SessionEvent::AdapterAdded(_)) => break,
+                Some(_) => continue,
+                None => bail!("bluez session closed while waiting for an adapter"),
+            }
+        }
+    }
 }
 
 pub fn spawn_worker() -> impl Stream<Item = WorkerEvent> {
     stream::channel(50, async move |mut output| {
-        let output_ = output.clone();
-        let worker = match BluetoothWorker::try_create(output_)
-            .await
-            .context("Could not create worker state")
-        {
-            Ok(w) => w,
-            Err(e) => {
-                _ = output.send(WorkerEvent::Error(format!{"{:?}", e})).await;
-                return;
+        let mut backoff = RESTART_BACKOFF_MIN;
+
+        // the worker only returns after reporting an error, so start over until bluez is back
+        loop {
+            match BluetoothWorker::try_create(output.clone())
+                .await
+                .context("Could not create worker state")
+            {
+                Ok(worker) => {
+                    // a worker that fails right after starting keeps backing off like one that can't start
+                    let started = tokio::time::Instant::now();
+                    worker.run().await;
+                    if started.elapsed() >= RESTART_BACKOFF_RESET {
+                        backoff = RESTART_BACKOFF_MIN;
+                    }
+                }
+                Err(e) => {
+                    _ = output.send(WorkerEvent::Error(format!("{:?}", e))).await;
+                }
             }
-        };
 
-        worker.run().await;
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(RESTART_BACKOFF_MAX);
+        }
     })
 }
 
@@ -299,6 +1586,15 @@ async fn device_listener(
             DeviceProperty::BatteryPercentage(battery) => DeviceUpdate::Battery(battery),
             DeviceProperty::Connected(connected) => DeviceUpdate::Connected(connected),
             DeviceProperty::Paired(paired) => DeviceUpdate::Paired(paired),
+            DeviceProperty::Trusted(trusted) => DeviceUpdate::Trusted(trusted),
+            DeviceProperty::Blocked(blocked) => DeviceUpdate::Blocked(blocked),
+            DeviceProperty::WakeAllowed(allowed) => DeviceUpdate::WakeAllowed(allowed),
+            // bluez updates the alias too when the name changes and no custom alias is set, so a Name change
+            // on its own must not overwrite a custom alias
+            DeviceProperty::Alias(alias) => DeviceUpdate::Name(alias),
+            DeviceProperty::Name(name) => DeviceUpdate::DeviceName(name),
+            DeviceProperty::Rssi(rssi) => DeviceUpdate::Rssi(rssi),
+            DeviceProperty::Icon(device_type) => DeviceUpdate::DeviceType(device_type),
             _ => continue,
         };
 
@@ -307,31 +1603,162 @@ async fn device_listener(
     }
 }
 
-async fn connect_with_retry(device: &bluer::Device) -> anyhow::Result<()> {
-    const MAX_TRIES: u32 = 5;
+/// Connects or disconnects the headset profiles a device offers, leaving audio playback alone
+async fn set_headset_profile(device: bluer::Device, enabled: bool) {
+    let uuids = device.uuids().await.ok().flatten().unwrap_or_default();
+
+    for profile in HEADSET_PROFILES.iter().filter(|p| uuids.contains(p)) {
+        let res = if enabled {
+            device.connect_profile(profile).await
+        } else {
+            device.disconnect_profile(profile).await
+        };
+
+        if let Err(e) = res {
+            tracing::warn!("could not change headset profile {profile} of {}: {e}", device.address());
+        }
+    }
+}
+
+/// Connects once a connect slot is free, every connect path should go through this.
+/// The timeout covers waiting for the slot too, it matches the countdown shown in the applet.
+async fn connect_limited(
+    limit: &Semaphore,
+    timeout: Duration,
+    retry: RetryPolicy,
+    device: &bluer::Device,
+) -> anyhow::Result<()> {
+    tokio::time::timeout(timeout, async {
+        let _permit = limit.acquire().await?;
+        connect_with_retry(retry, device).await
+    })
+    .await
+    .context("connect timed out")?
+}
+
+/// Re-reads the paired state after a connect. Some devices finish pairing without bluez emitting a Paired change,
+/// which would leave them stuck under "other devices"
+async fn report_paired(
+    addr: bluer::Address,
+    is_paired: impl Future<Output = bluer::Result<bool>>,
+    device_tx: &mpsc::UnboundedSender<(bluer::Address, DeviceUpdate)>,
+) {
+    if is_paired.await.unwrap_or_default() {
+        _ = device_tx.send((addr, DeviceUpdate::Paired(true)));
+    }
+}
+
+/// Source of the delays used by retry and timeout logic, so that logic can be driven without real waiting
+pub(crate) trait Clock {
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send;
+}
+
+/// The real clock, backed by the tokio timer
+pub(crate) struct TokioClock;
+
+impl Clock for TokioClock {
+    fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send {
+        tokio::time::sleep(duration)
+    }
+}
+
+async fn connect_with_retry(retry: RetryPolicy, device: &bluer::Device) -> anyhow::Result<()> {
+    retry_with_backoff(&TokioClock, retry, || device.connect()).await
+}
+
+/// Runs `handle` with the request, and if the bluez connection dropped underneath it, reconnects and runs it once
+/// more when the request is `retryable`. bluetoothd may still be coming back up, so reconnecting is retried with
+/// backoff
+async fn handle_reconnecting<S, R: Clone + std::fmt::Debug>(
+    clock: &impl Clock,
+    state: &mut S,
+    request: R,
+    retryable: bool,
+    mut handle: impl AsyncFnMut(&mut S, R) -> anyhow::Result<()>,
+    mut reconnect: impl AsyncFnMut(&mut S) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    match handle(state, request.clone()).await {
+        Err(e) if is_disconnected(&e) => {
+            tracing::warn!("lost connection to bluez while handling {request:?}, reconnecting");
+
+            // the closures can't go through `retry_with_backoff`, their futures borrow `state`
+            let mut attempt = 0;
+            let mut backoff = RECONNECT_RETRY.initial_backoff;
+            loop {
+                attempt += 1;
+                match reconnect(state).await {
+                    Ok(()) => break,
+                    Err(e) if attempt >= RECONNECT_RETRY.max_tries || !is_disconnected(&e) => {
+                        return Err(e);
+                    }
+                    Err(_) => {
+                        clock.sleep(backoff).await;
+                        backoff = (backoff * 2).min(RECONNECT_RETRY.max_backoff);
+                    }
+                }
+            }
+
+            if retryable {
+                handle(state, request).await
+            } else {
+                Ok(())
+            }
+        }
+        res => res,
+    }
+}
+
+/// Runs `op` until it succeeds, sleeping with exponential backoff between attempts
+async fn retry_with_backoff<T, E, F, Fut>(
+    clock: &impl Clock,
+    policy: RetryPolicy,
+    mut op: F,
+) -> anyhow::Result<T>
+where
+    E: std::error::Error + Send + Sync + 'static,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
     let mut attempt = 0;
-    let mut backoff = Duration::from_millis(500);
+    let mut backoff = policy.initial_backoff;
 
     loop {
         attempt += 1;
 
-        match device.connect().await {
-            Ok(_) => return Ok(()),
+        match op().await {
+            Ok(v) => return Ok(v),
             Err(e) => {
-                if attempt >= MAX_TRIES {
+                if attempt >= policy.max_tries {
                     bail!(e)
                 }
 
-                // Exponential backoff with max of 10 seconds
-                tokio::time::sleep(backoff).await;
-                backoff = (backoff * 2).min(Duration::from_secs(10));
+                // Exponential backoff up to the configured max
+                clock.sleep(backoff).await;
+                backoff = (backoff * 2).min(policy.max_backoff);
             }
         }
     }
 }
 
 fn find_adapter_idx(adapter_name: &str) -> anyhow::Result<u32> {
-    for entry in std::fs::read_dir("/sys/class/rfkill")? {
+    find_rfkill_idx(Path::new("/sys/class/rfkill"), adapter_name)
+}
+
+/// Finds the rfkill index of an adapter in an rfkill sysfs directory. The rfkill name doesn't always match the
+/// adapter's, so the radio's parent device is tried too, and a lone bluetooth radio is assumed to be the
+/// adapter's. Names only have to match up to case and separators
+fn find_rfkill_idx(rfkill_dir: &Path, adapter_name: &str) -> anyhow::Result<u32> {
+    // the whole name has to match, `hci1` is a prefix of `hci10`
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_lowercase()
+    };
+
+    // (index, name, name of the parent device)
+    let mut radios = Vec::new();
+    for entry in std::fs::read_dir(rfkill_dir)? {
         let entry = entry?;
         let path = entry.path();
 
@@ -343,20 +1770,33 @@ fn find_adapter_idx(adapter_name: &str) -> anyhow::Result<u32> {
             continue;
         }
 
-        let name_match = std::fs::read_to_string(path.join("name"))
-            .map(|t| t.trim() == adapter_name)
-            .unwrap_or(false);
-
-        if name_match {
-            let idx = std::fs::read_to_string(path.join("index"))?
-                .trim()
-                .parse::<u32>()?;
+        // a radio that's going away can't be switched anyway, the others are still worth a look
+        let Some(idx) = std::fs::read_to_string(path.join("index"))
+            .ok()
+            .and_then(|i| i.trim().parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let name = std::fs::read_to_string(path.join("name"))
+            .map(|t| normalize(&t))
+            .unwrap_or_default();
+        // links to the hci device the radio belongs to
+        let device = std::fs::read_link(path.join("device"))
+            .ok()
+            .and_then(|p| p.file_name().map(|n| normalize(&n.to_string_lossy())));
 
-            return Ok(idx);
-        }
+        radios.push((idx, name, device));
     }
 
-    bail!("No rfkill bluetooth device with name {}", adapter_name)
+    let wanted = normalize(adapter_name);
+    let found = radios.iter().find(|(_, name, device)| {
+        !wanted.is_empty() && (*name == wanted || device.as_ref() == Some(&wanted))
+    });
+
+    match (found, radios.as_slice()) {
+        (Some((idx, ..)), _) | (None, [(idx, ..)]) => Ok(*idx),
+        (None, _) => bail!("No rfkill bluetooth device with name {}", adapter_name),
+    }
 }
 
 fn rfkill_set_enabled(idx: u32, enable: bool) -> anyhow::Result<()> {
@@ -364,24 +1804,117 @@ fn rfkill_set_enabled(idx: u32, enable: bool) -> anyhow::Result<()> {
         .write(true)
         .open("/dev/rfkill")?;
 
+    file.write_all(&rfkill_change_event(idx, enable).to_bytes())?;
+
+    Ok(())
+}
+
+/// The event that soft blocks or unblocks the bluetooth radio `idx`
+fn rfkill_change_event(idx: u32, enable: bool) -> RfkillEvent {
     // see https://github.com/torvalds/linux/blob/master/include/uapi/linux/rfkill.h
-    let event = RfkillEvent {
+    RfkillEvent {
         idx,
-        _type: 2, // RFKILL_TYPE_BLUETOOTH
-        op: 2,    // RFKILL_OP_CHANGE,
+        _type: RFKILL_TYPE_BLUETOOTH,
+        op: 2, // RFKILL_OP_CHANGE,
         soft: if enable { 0 } else { 1 },
         hard: 0,
-    };
+    }
+}
 
-    let bytes: [u8; std::mem::size_of::<RfkillEvent>()] = unsafe { std::mem::transmute(event) };
-    file.write_all(&bytes)?;
+/// Streams the rfkill state of bluetooth radios, as the radio index and whether it is soft and hard blocked. The
+/// kernel reports the current state of every radio first, then each change. Dropping the stream closes the device
+fn rfkill_events() -> std::io::Result<impl Stream<Item = (u32, bool, bool)> + Send> {
+    let fd = rustix::fs::open(
+        "/dev/rfkill",
+        rustix::fs::OFlags::RDONLY | rustix::fs::OFlags::NONBLOCK | rustix::fs::OFlags::CLOEXEC,
+        rustix::fs::Mode::empty(),
+    )?;
+    let file = tokio::io::unix::AsyncFd::new(std::fs::File::from(fd))?;
 
-    Ok(())
+    Ok(futures::stream::unfold(file, |file| async move {
+        // newer kernels send longer events, the original fields come first
+        let mut buf = [0; 64];
+        loop {
+            let read = match file.readable().await {
+                Ok(mut guard) => guard.try_io(|f| f.get_ref().read(&mut buf)),
+                Err(e) => Ok(Err(e)),
+            };
+            let len = match read {
+                Ok(Ok(0)) => return None,
+                Ok(Ok(len)) => len,
+                Ok(Err(e)) => {
+                    tracing::warn!("could not read rfkill event: {e}");
+                    return None;
+                }
+                // woken without an event to read
+                Err(_) => continue,
+            };
+            if len < RFKILL_EVENT_SIZE_V1 {
+                continue;
+            }
+
+            let mut bytes = [0; RFKILL_EVENT_SIZE_V1];
+            bytes.copy_from_slice(&buf[..RFKILL_EVENT_SIZE_V1]);
+            let event = RfkillEvent::from_bytes(bytes);
+            if event._type != RFKILL_TYPE_BLUETOOTH {
+                continue;
+            }
+
+            return Some(((event.idx, event.soft != 0, event.hard != 0), file));
+        }
+    }))
+}
+
+/// Adds the paired devices of every adapter except `selected` to the device maps,
+/// returning the adapter each added device belongs to
+async fn add_foreign_devices(
+    session: &bluer::Session,
+    selected: &bluer::Adapter,
+    device_tx: &mpsc::UnboundedSender<(bluer::Address, DeviceUpdate)>,
+    device_map: &mut HashMap<bluer::Address, BluetoothDevice>,
+    device_handles: &mut HashMap<bluer::Address, tokio::task::JoinHandle<()>>,
+) -> anyhow::Result<HashMap<bluer::Address, String>> {
+    let mut foreign_devices = HashMap::new();
+
+    for name in session.adapter_names().await? {
+        if name == selected.name() {
+            continue;
+        }
+
+        let adapter = session.adapter(&name)?;
+        for addr in adapter.device_addresses().await? {
+            // paired with both adapters, the selected one wins
+            if device_map.contains_key(&addr) {
+                continue;
+            }
+
+            let device = adapter.device(addr)?;
+            if !device.is_paired().await? {
+                continue;
+            }
+
+            let mut bt_device = BluetoothDevice::from_device(&device).await;
+            bt_device.adapter = Some(name.clone());
+
+            let events = device.events().await?;
+            let output = device_tx.clone();
+            device_handles.insert(
+                addr,
+                tokio::spawn(async move { device_listener(addr, events, output).await }),
+            );
+            device_map.insert(addr, bt_device);
+            foreign_devices.insert(addr, name.clone());
+        }
+    }
+
+    Ok(foreign_devices)
 }
 
+/// Reads every device of the adapter, sending each to the app as soon as it's read
 async fn create_device_maps(
     adapter: &bluer::Adapter,
     device_tx: &mpsc::UnboundedSender<(bluer::Address, DeviceUpdate)>,
+    output: &mut futures::channel::mpsc::Sender<WorkerEvent>,
 ) -> anyhow::Result<(
     HashMap<bluer::Address, BluetoothDevice>,
     HashMap<bluer::Address, tokio::task::JoinHandle<()>>,
@@ -393,29 +1926,355 @@ async fn create_device_maps(
         .map(async |addr| {
             let device = adapter.device(addr)?;
             let bt_device = BluetoothDevice::from_device(&device).await;
-
-            if device.name().await?.is_none() && bt_device.icon == DEFAULT_DEVICE_ICON {
-                return Ok(None);
-            }
-
             let events = device.events().await?;
             let addr_ = addr.clone();
             let output = device_tx.clone();
-            Ok::<_, bluer::Error>(Some((
+            Ok::<_, bluer::Error>((
                 addr,
                 bt_device,
                 tokio::spawn(async move { device_listener(addr_, events, output).await }),
-            )))
+            ))
         })
         .collect::<FuturesUnordered<_>>();
 
     let mut device_handles = HashMap::new();
     let mut device_map = HashMap::new();
 
-    while let Some((addr, bt_device, handle)) = futures.try_next().await?.flatten() {
+    while let Some((addr, bt_device, handle)) = futures.try_next().await? {
+        _ = output.send(WorkerEvent::DeviceAdded(bt_device.clone())).await;
         device_map.insert(addr.clone(), bt_device);
         device_handles.insert(addr, handle);
     }
 
     Ok((device_map, device_handles))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dbus_error(name: &str) -> bluer::Error {
+        bluer::Error {
+            kind: bluer::ErrorKind::Internal(bluer::InternalErrorKind::DBus(name.to_string())),
+            message: String::new(),
+        }
+    }
+
+    #[test]
+    fn session_lost_during_request_is_disconnected() {
+        // the way a failed request reaches `handle_request_reconnecting`
+        let err = anyhow::Error::from(dbus_error("org.freedesktop.DBus.Error.NoReply"))
+            .context("Could not connect device");
+        assert!(is_disconnected(&err));
+    }
+
+    /// How often a fake request and reconnect ran
+    #[derive(Default)]
+    struct Calls {
+        handled: u32,
+        reconnects: u32,
+    }
+
+    fn session_lost() -> anyhow::Error {
+        dbus_error("org.freedesktop.DBus.Error.NoReply").into()
+    }
+
+    #[test]
+    fn session_lost_during_request_reconnects_and_retries() {
+        let clock = FakeClock::default();
+        let mut calls = Calls::default();
+        let res = futures::executor::block_on(handle_reconnecting(
+            &clock,
+            &mut calls,
+            (),
+            true,
+            async |calls: &mut Calls, ()| {
+                calls.handled += 1;
+                if calls.handled == 1 {
+                    Err(session_lost())
+                } else {
+                    Ok(())
+                }
+            },
+            // bluetoothd takes a moment to come back
+            async |calls: &mut Calls| {
+                calls.reconnects += 1;
+                if calls.reconnects < 3 {
+                    Err(session_lost())
+                } else {
+                    Ok(())
+                }
+            },
+        ));
+
+        assert!(res.is_ok());
+        assert_eq!(calls.handled, 2);
+        assert_eq!(calls.reconnects, 3);
+        assert_eq!(
+            *clock.sleeps.lock().unwrap(),
+            [500, 1000].map(Duration::from_millis)
+        );
+    }
+
+    #[test]
+    fn session_lost_during_unsafe_request_is_not_repeated() {
+        let clock = FakeClock::default();
+        let mut calls = Calls::default();
+        let res = futures::executor::block_on(handle_reconnecting(
+            &clock,
+            &mut calls,
+            (),
+            false,
+            async |calls: &mut Calls, ()| {
+                calls.handled += 1;
+                Err(session_lost())
+            },
+            async |calls: &mut Calls| {
+                calls.reconnects += 1;
+                Ok(())
+            },
+        ));
+
+        assert!(res.is_ok());
+        assert_eq!(calls.handled, 1);
+        assert_eq!(calls.reconnects, 1);
+        assert!(clock.sleeps.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn reconnect_gives_up_after_its_tries() {
+        let clock = FakeClock::default();
+        let mut calls = Calls::default();
+        let res = futures::executor::block_on(handle_reconnecting(
+            &clock,
+            &mut calls,
+            (),
+            true,
+            async |calls: &mut Calls, ()| {
+                calls.handled += 1;
+                Err(session_lost())
+            },
+            async |calls: &mut Calls| {
+                calls.reconnects += 1;
+                Err(session_lost())
+            },
+        ));
+
+        assert!(res.is_err_and(|e| is_disconnected(&e)));
+        assert_eq!(calls.handled, 1);
+        assert_eq!(calls.reconnects, RECONNECT_RETRY.max_tries);
+        assert_eq!(
+            *clock.sleeps.lock().unwrap(),
+            [500, 1000, 2000].map(Duration::from_millis)
+        );
+    }
+
+    #[test]
+    fn failed_request_does_not_reconnect() {
+        let clock = FakeClock::default();
+        let mut calls = Calls::default();
+        let res = futures::executor::block_on(handle_reconnecting(
+            &clock,
+            &mut calls,
+            (),
+            true,
+            async |calls: &mut Calls, ()| {
+                calls.handled += 1;
+                Err(dbus_error("org.freedesktop.DBus.Error.AccessDenied").into())
+            },
+            async |calls: &mut Calls| {
+                calls.reconnects += 1;
+                Ok(())
+            },
+        ));
+
+        assert!(res.is_err());
+        assert_eq!(calls.handled, 1);
+        assert_eq!(calls.reconnects, 0);
+    }
+
+    #[test]
+    fn failed_operation_is_not_disconnected() {
+        let err = anyhow::Error::from(bluer::Error {
+            kind: bluer::ErrorKind::Failed,
+            message: "org.freedesktop.DBus.Error.NoReply".to_string(),
+        });
+        assert!(!is_disconnected(&err));
+        let err = anyhow::Error::from(dbus_error("org.freedesktop.DBus.Error.AccessDenied"));
+        assert!(!is_disconnected(&err));
+    }
+
+    /// Lays out an rfkill sysfs directory with one bluetooth radio per (index, name, parent device)
+    fn rfkill_dir(test: &str, radios: &[(&str, &str, Option<&str>)]) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "cosmic-applet-bluetooth-{test}-{}",
+            std::process::id()
+        ));
+        _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (i, (idx, name, device)) in radios.iter().enumerate() {
+            let radio = dir.join(format!("rfkill{i}"));
+            std::fs::create_dir(&radio).unwrap();
+            std::fs::write(radio.join("type"), "bluetooth\n").unwrap();
+            std::fs::write(radio.join("index"), format!("{idx}\n")).unwrap();
+            std::fs::write(radio.join("name"), format!("{name}\n")).unwrap();
+            if let Some(device) = device {
+                std::os::unix::fs::symlink(format!("../../{device}"), radio.join("device"))
+                    .unwrap();
+            }
+        }
+
+        dir
+    }
+
+    #[test]
+    fn rfkill_lone_radio_is_the_adapters() {
+        let dir = rfkill_dir("lone", &[("0", "phy0", None)]);
+        assert_eq!(find_rfkill_idx(&dir, "hci0").unwrap(), 0);
+        _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rfkill_name_mismatch_falls_back_to_parent_device() {
+        let dir = rfkill_dir(
+            "mismatch",
+            &[("1", "phy0", Some("hci0")), ("2", "phy1", Some("hci1"))],
+        );
+        assert_eq!(find_rfkill_idx(&dir, "hci1").unwrap(), 2);
+        assert_eq!(find_rfkill_idx(&dir, "HCI0").unwrap(), 1);
+        assert!(find_rfkill_idx(&dir, "hci2").is_err());
+        _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rfkill_two_adapters_match_whole_names() {
+        let dir = rfkill_dir("two", &[("10", "hci10", None), ("1", "hci1", None)]);
+        assert_eq!(find_rfkill_idx(&dir, "hci1").unwrap(), 1);
+        assert_eq!(find_rfkill_idx(&dir, "hci10").unwrap(), 10);
+        _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rfkill_unreadable_index_is_skipped() {
+        let dir = rfkill_dir(
+            "unreadable",
+            &[
+                ("gone", "hci0", None),
+                ("4", "hci1", None),
+                ("5", "hci2", None),
+            ],
+        );
+        assert_eq!(find_rfkill_idx(&dir, "hci1").unwrap(), 4);
+        assert!(find_rfkill_idx(&dir, "hci0").is_err());
+        _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rfkill_event_matches_kernel_layout() {
+        let event = RfkillEvent {
+            idx: 0x0403_0201,
+            _type: 5,
+            op: 6,
+            soft: 7,
+            hard: 8,
+        };
+        let bytes = event.to_bytes();
+
+        // a host order __u32 index, then the __u8 type, op, soft and hard fields
+        assert_eq!(bytes[..4], 0x0403_0201u32.to_ne_bytes());
+        assert_eq!(bytes[4..], [5, 6, 7, 8]);
+        assert_eq!(RfkillEvent::from_bytes(bytes).to_bytes(), bytes);
+    }
+
+    #[test]
+    #[cfg(target_endian = "little")]
+    fn rfkill_change_events_serialize_to_kernel_bytes() {
+        // idx, type bluetooth, op change, soft, hard
+        assert_eq!(
+            rfkill_change_event(3, true).to_bytes(),
+            [3, 0, 0, 0, 2, 2, 0, 0]
+        );
+        assert_eq!(
+            rfkill_change_event(3, false).to_bytes(),
+            [3, 0, 0, 0, 2, 2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn pairing_without_paired_event_is_reported() {
+        let addr = bluer::Address::new([1, 2, 3, 4, 5, 6]);
+        // nothing but the worker itself sends on the channel, as when bluez never emits the Paired change
+        let (device_tx, mut device_rx) = mpsc::unbounded_channel();
+
+        futures::executor::block_on(report_paired(addr, async { Ok(true) }, &device_tx));
+        assert!(matches!(
+            device_rx.try_recv(),
+            Ok((a, DeviceUpdate::Paired(true))) if a == addr
+        ));
+
+        futures::executor::block_on(report_paired(addr, async { Ok(false) }, &device_tx));
+        futures::executor::block_on(report_paired(
+            addr,
+            async { Err(dbus_error("org.bluez.Error.DoesNotExist")) },
+            &device_tx,
+        ));
+        assert!(device_rx.try_recv().is_err());
+    }
+
+    /// Records the requested sleeps and returns right away
+    #[derive(Default)]
+    struct FakeClock {
+        sleeps: std::sync::Mutex<Vec<Duration>>,
+    }
+
+    impl Clock for FakeClock {
+        fn sleep(&self, duration: Duration) -> impl Future<Output = ()> + Send {
+            self.sleeps.lock().unwrap().push(duration);
+            std::future::ready(())
+        }
+    }
+
+    fn policy(max_tries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_tries,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+        }
+    }
+
+    #[test]
+    fn retry_backs_off_until_out_of_tries() {
+        let clock = FakeClock::default();
+        let mut attempts = 0;
+        let res: anyhow::Result<()> =
+            futures::executor::block_on(retry_with_backoff(&clock, policy(4), || {
+                attempts += 1;
+                std::future::ready(Err(std::io::Error::other("busy")))
+            }));
+
+        assert!(res.is_err());
+        assert_eq!(attempts, 4);
+        assert_eq!(
+            *clock.sleeps.lock().unwrap(),
+            [100, 200, 300].map(Duration::from_millis)
+        );
+    }
+
+    #[test]
+    fn retry_stops_at_first_success() {
+        let clock = FakeClock::default();
+        let mut attempts = 0;
+        let res = futures::executor::block_on(retry_with_backoff(&clock, policy(4), || {
+            attempts += 1;
+            std::future::ready(if attempts < 2 {
+                Err(std::io::Error::other("busy"))
+            } else {
+                Ok(attempts)
+            })
+        }));
+
+        assert_eq!(res.unwrap(), 2);
+        assert_eq!(*clock.sleeps.lock().unwrap(), [Duration::from_millis(100)]);
+    }
+}